@@ -0,0 +1,37 @@
+// The colors of a column: its cells are formatted once and their roles read from
+// the raw and formatted values, instead of matching every cell against regexes
+// each time the table is printed.
+//
+//   cargo bench --bench cell_roles
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use tidy_viewer::datatype::{cell_roles, format_strings, FormatOptions};
+
+// A header and 10,000 values, numbers mixed with negatives, missing values and text.
+fn column() -> Vec<String> {
+    let mut column = vec!["price".to_string()];
+    column.extend((0..10_000).map(|i| match i % 5 {
+        0 => format!("{}.{}", i, i % 97),
+        1 => format!("-{}.5", i),
+        2 => "NA".to_string(),
+        3 => String::new(),
+        _ => format!("item {}", i),
+    }));
+    column
+}
+
+fn bench_cell_roles(c: &mut Criterion) {
+    let column = column();
+    let raw: Vec<&str> = column.iter().map(String::as_str).collect();
+    let opts = FormatOptions::default();
+    let shown = format_strings(&raw, &opts);
+
+    c.bench_function("format_strings", |b| {
+        b.iter(|| format_strings(black_box(&raw), &opts))
+    });
+    c.bench_function("cell_roles", |b| {
+        b.iter(|| cell_roles(black_box(&raw), black_box(&shown), &opts))
+    });
+}
+
+criterion_group!(benches, bench_cell_roles);
+criterion_main!(benches);
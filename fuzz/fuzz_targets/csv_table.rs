@@ -0,0 +1,62 @@
+// Feeds arbitrary bytes through the path of a csv file in tv: the csv reader that
+// build_reader makes, column formatting and the fit of columns to a
+// terminal. Any panic is a bug, malformed input only ever makes rows to skip.
+//
+//     cargo +nightly fuzz run csv_table -- -dict=fuzz/csv.dict
+//
+// The first bytes pick the options, so narrow columns that truncate text and
+// small terminals are reached as well as the defaults.
+#![no_main]
+
+use csv::StringRecord;
+use libfuzzer_sys::fuzz_target;
+use tidy_viewer::{datatype, layout};
+
+const DELIMITERS: &[u8] = b",\t|;";
+
+fuzz_target!(|data: &[u8]| {
+    let [delimiter, lower, upper, sigfig, term_width, input @ ..] = data else {
+        return;
+    };
+    // the column widths that main accepts: lower is at least 2 and upper larger
+    let lower = 2 + *lower as usize % 8;
+    let opts = datatype::FormatOptions {
+        lower_column_width: lower,
+        upper_column_width: lower + 1 + *upper as usize % 32,
+        sigfig: 1 + *sigfig as i64 % datatype::MAX_SIGFIG,
+        na_strings: sigfig & 0x80 == 0,
+        ..Default::default()
+    };
+
+    let delimiter = DELIMITERS[*delimiter as usize % DELIMITERS.len()];
+    let mut reader = datatype::csv_reader(input, delimiter, true, None);
+    // like --skip-invalid-rows, rows that are not utf-8 are left out
+    let records: Vec<StringRecord> = reader.records().filter_map(Result::ok).collect();
+    if records.is_empty() {
+        return;
+    }
+
+    let cols = records[0].len();
+    let columns: Vec<Vec<&str>> = (0..cols)
+        .map(|col| {
+            records
+                .iter()
+                .map(|row| row.get(col).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    let formatted: Vec<Vec<String>> = columns
+        .iter()
+        .map(|col| datatype::format_strings(col, &opts))
+        .collect();
+    for (col, shown) in columns.iter().zip(&formatted) {
+        assert_eq!(col.len(), shown.len());
+        for (raw, shown) in col.iter().zip(shown) {
+            datatype::is_rounded(raw, shown.trim());
+        }
+    }
+
+    let widths: Vec<usize> = formatted.iter().map(|col| col[0].chars().count()).collect();
+    let fit = layout::fit(&widths, *term_width as usize, opts.gutter_width + 2);
+    assert_eq!(fit.widths.len() + fit.dropped.len(), cols);
+});
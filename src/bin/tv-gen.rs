@@ -0,0 +1,268 @@
+// Makes csv files with values that are tricky to show, for benchmarks and bug
+// reports: unicode, long decimals, NAs, quoting and, on request, ragged rows.
+//
+//     cargo run --features gen --bin tv-gen -- --rows 1e6 --cols 50 --out test.csv
+//
+// The same --seed always gives the same file.
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "tv-gen", about = "Generate csv test data for tv")]
+struct Cli {
+    #[structopt(
+        long = "rows",
+        default_value = "1000",
+        parse(try_from_str = parse_count),
+        help = "Number of data rows. May be written like 1e6"
+    )]
+    rows: usize,
+
+    #[structopt(
+        long = "cols",
+        default_value = "10",
+        parse(try_from_str = parse_count),
+        help = "Number of columns"
+    )]
+    cols: usize,
+
+    #[structopt(
+        long = "types",
+        default_value = "mixed",
+        help = "The column types: mixed (every kind in turn), numeric or text"
+    )]
+    types: Types,
+
+    #[structopt(
+        long = "ragged",
+        help = "Make about one row in a hundred short or long"
+    )]
+    ragged: bool,
+
+    #[structopt(long = "seed", default_value = "1", help = "Seed of the random values")]
+    seed: u64,
+
+    #[structopt(
+        long = "out",
+        parse(from_os_str),
+        help = "The csv file to write. Written to stdout when not given"
+    )]
+    out: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Types {
+    Mixed,
+    Numeric,
+    Text,
+}
+
+impl std::str::FromStr for Types {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mixed" => Ok(Types::Mixed),
+            "numeric" => Ok(Types::Numeric),
+            "text" => Ok(Types::Text),
+            _ => Err(format!("expected mixed, numeric or text, got \"{}\"", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Integer,
+    Double,
+    LongDecimal,
+    Text,
+    Logical,
+    Date,
+    DateTime,
+}
+
+const NUMERIC: &[Kind] = &[Kind::Integer, Kind::Double, Kind::LongDecimal];
+const TEXT: &[Kind] = &[Kind::Text];
+const MIXED: &[Kind] = &[
+    Kind::Integer,
+    Kind::Double,
+    Kind::Text,
+    Kind::LongDecimal,
+    Kind::Logical,
+    Kind::Date,
+    Kind::DateTime,
+];
+
+const WORDS: &[&str] = &[
+    "alpha",
+    "Zoë",
+    "naïve café",
+    "東京",
+    "데이터",
+    "🦀 crab",
+    "e\u{301}clair",
+    "comma, inside",
+    "\"quoted\"",
+    "two\nlines",
+    "   padded   ",
+    "a much longer piece of free text that is wider than any column",
+];
+
+const NAS: &[&str] = &["", "NA", "null", "N/A", "NaN"];
+
+// splitmix64, so the output does not change with a dependency
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+fn value(kind: Kind, rng: &mut Rng) -> String {
+    // one value in twenty is missing
+    if rng.below(20) == 0 {
+        return rng.pick(NAS).to_string();
+    }
+    let sign = if rng.below(4) == 0 { "-" } else { "" };
+    match kind {
+        Kind::Integer => {
+            let digits = rng.below(10) as u32 + 1;
+            format!("{}{}", sign, rng.below(10u64.pow(digits)))
+        }
+        Kind::Double => format!(
+            "{}{}.{:0w$}",
+            sign,
+            rng.below(10_000),
+            rng.below(1_000),
+            w = 3
+        ),
+        Kind::LongDecimal => format!("{}0.{:018}", sign, rng.below(10u64.pow(18))),
+        Kind::Text => rng.pick(WORDS).to_string(),
+        Kind::Logical => rng
+            .pick(&["TRUE", "FALSE", "T", "F", "true", "false"])
+            .to_string(),
+        Kind::Date => format!(
+            "{}-{:02}-{:02}",
+            1970 + rng.below(60),
+            1 + rng.below(12),
+            1 + rng.below(28)
+        ),
+        Kind::DateTime => format!(
+            "{}-{:02}-{:02} {:02}:{:02}:{:02}",
+            1970 + rng.below(60),
+            1 + rng.below(12),
+            1 + rng.below(28),
+            rng.below(24),
+            rng.below(60),
+            rng.below(60)
+        ),
+    }
+}
+
+fn parse_count(src: &str) -> Result<usize, String> {
+    match src.parse::<f64>() {
+        Ok(n) if n >= 0.0 && n.fract() == 0.0 && n <= usize::MAX as f64 => Ok(n as usize),
+        _ => Err(format!(
+            "expected a count like 1000 or 1e6, got \"{}\"",
+            src
+        )),
+    }
+}
+
+fn generate(opt: &Cli, out: impl Write) -> csv::Result<()> {
+    let kinds = match opt.types {
+        Types::Mixed => MIXED,
+        Types::Numeric => NUMERIC,
+        Types::Text => TEXT,
+    };
+    let columns: Vec<Kind> = (0..opt.cols).map(|i| kinds[i % kinds.len()]).collect();
+    let mut rng = Rng(opt.seed);
+    let mut writer = csv::WriterBuilder::new()
+        .flexible(opt.ragged)
+        .from_writer(out);
+
+    let header: Vec<String> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, kind)| format!("{:?}_{}", kind, i + 1).to_lowercase())
+        .collect();
+    writer.write_record(&header)?;
+    for _ in 0..opt.rows {
+        let mut row: Vec<String> = columns.iter().map(|&kind| value(kind, &mut rng)).collect();
+        if opt.ragged && rng.below(100) == 0 {
+            if rng.below(2) == 0 {
+                row.truncate(rng.below(row.len() as u64) as usize);
+            } else {
+                row.push(value(Kind::Text, &mut rng));
+            }
+        }
+        writer.write_record(&row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn main() {
+    let opt = Cli::from_args();
+    let result = match &opt.out {
+        Some(path) if path.extension().is_some_and(|ext| ext != "csv") => {
+            eprintln!("{}: tv-gen only writes csv files", path.display());
+            std::process::exit(1);
+        }
+        Some(path) => match File::create(path) {
+            Ok(file) => generate(&opt, io::BufWriter::new(file)),
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        None => generate(&opt, io::stdout().lock()),
+    };
+    if let Err(e) = result {
+        match e.kind() {
+            csv::ErrorKind::Io(e) if e.kind() == io::ErrorKind::BrokenPipe => {}
+            _ => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_and_seeds() {
+        assert_eq!(parse_count("1e6"), Ok(1_000_000));
+        assert_eq!(parse_count("50"), Ok(50));
+        assert!(parse_count("1.5").is_err());
+        assert!(parse_count("-1").is_err());
+
+        let csv = |seed| {
+            let opt = Cli::from_iter(["tv-gen", "--rows", "20", "--seed", seed]);
+            let mut out = Vec::new();
+            generate(&opt, &mut out).unwrap();
+            out
+        };
+        assert_eq!(csv("7"), csv("7"));
+        assert_ne!(csv("7"), csv("8"));
+        assert!(csv("7").iter().filter(|&&b| b == b'\n').count() >= 21);
+    }
+}
@@ -0,0 +1,143 @@
+// Terminal color support. Palettes are defined as RGB; terminals without
+// truecolor get the nearest of the 16 standard ANSI colors instead.
+use owo_colors::{AnsiColors, DynColors};
+
+/// The kind of color escape codes to emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    TrueColor,
+    Ansi16,
+}
+
+impl std::str::FromStr for ColorDepth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "truecolor" | "24bit" => Ok(ColorDepth::TrueColor),
+            "16" | "ansi" => Ok(ColorDepth::Ansi16),
+            _ => Err(format!(
+                "unknown color depth \"{}\", expected truecolor or 16",
+                s
+            )),
+        }
+    }
+}
+
+/// Guess the color depth from `$COLORTERM` and `$TERM`. Truecolor unless the
+/// terminal is known to only have 16 colors (the Linux console, `*-16color`).
+pub fn detect_depth(colorterm: Option<&str>, term: Option<&str>) -> ColorDepth {
+    if let Some("truecolor" | "24bit") = colorterm {
+        return ColorDepth::TrueColor;
+    }
+    match term {
+        Some(t) if t == "linux" || t == "ansi" || t.ends_with("-16color") => ColorDepth::Ansi16,
+        _ => ColorDepth::TrueColor,
+    }
+}
+
+/// Can the terminal show escape codes at all? On Windows this also switches
+/// on virtual terminal processing, which older consoles need before they
+/// interpret escape codes instead of printing them.
+pub fn ansi_supported() -> bool {
+    #[cfg(windows)]
+    {
+        crossterm::ansi_support::supports_ansi()
+    }
+    #[cfg(not(windows))]
+    {
+        true
+    }
+}
+
+/// The ANSI color closest in hue to `rgb`. Nearly gray colors map to the
+/// four grays; otherwise the channels above the midpoint pick the hue, so muted
+/// palettes keep their hue instead of all collapsing to gray.
+pub fn nearest_ansi(rgb: [u8; 3]) -> AnsiColors {
+    let [r, g, b] = rgb.map(i32::from);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    if max - min < 40 {
+        return match max {
+            0..=63 => AnsiColors::Black,
+            64..=159 => AnsiColors::BrightBlack,
+            160..=223 => AnsiColors::White,
+            _ => AnsiColors::BrightWhite,
+        };
+    }
+    let mid = (max + min) / 2;
+    let bright = max > 200;
+    match (r > mid, g > mid, b > mid, bright) {
+        (true, false, false, false) => AnsiColors::Red,
+        (true, false, false, true) => AnsiColors::BrightRed,
+        (false, true, false, false) => AnsiColors::Green,
+        (false, true, false, true) => AnsiColors::BrightGreen,
+        (true, true, false, false) => AnsiColors::Yellow,
+        (true, true, false, true) => AnsiColors::BrightYellow,
+        (false, false, true, false) => AnsiColors::Blue,
+        (false, false, true, true) => AnsiColors::BrightBlue,
+        (true, false, true, false) => AnsiColors::Magenta,
+        (true, false, true, true) => AnsiColors::BrightMagenta,
+        (false, true, true, false) => AnsiColors::Cyan,
+        (false, true, true, true) => AnsiColors::BrightCyan,
+        // unreachable: max is above the midpoint and min is not
+        _ => AnsiColors::White,
+    }
+}
+
+/// The color to paint `rgb` with at the given depth.
+pub fn to_dyn(rgb: [u8; 3], depth: ColorDepth) -> DynColors {
+    match depth {
+        ColorDepth::TrueColor => DynColors::Rgb(rgb[0], rgb[1], rgb[2]),
+        ColorDepth::Ansi16 => DynColors::Ansi(nearest_ansi(rgb)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use owo_colors::OwoColorize;
+
+    #[test]
+    fn detects_depth_from_env() {
+        assert_eq!(detect_depth(None, None), ColorDepth::TrueColor);
+        assert_eq!(
+            detect_depth(None, Some("xterm-256color")),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(detect_depth(None, Some("linux")), ColorDepth::Ansi16);
+        assert_eq!(
+            detect_depth(None, Some("xterm-16color")),
+            ColorDepth::Ansi16
+        );
+        assert_eq!(
+            detect_depth(Some("truecolor"), Some("linux")),
+            ColorDepth::TrueColor
+        );
+        assert_eq!("16".parse(), Ok(ColorDepth::Ansi16));
+        assert!("256".parse::<ColorDepth>().is_err());
+    }
+
+    #[test]
+    fn falls_back_to_16_colors() {
+        // nord palette
+        assert!(matches!(nearest_ansi([191, 97, 106]), AnsiColors::Red));
+        assert!(matches!(nearest_ansi([143, 188, 187]), AnsiColors::Cyan));
+        assert!(matches!(nearest_ansi([94, 129, 172]), AnsiColors::Blue));
+        assert!(matches!(
+            nearest_ansi([216, 222, 233]),
+            AnsiColors::BrightWhite
+        ));
+        assert!(matches!(
+            nearest_ansi([226, 125, 95]),
+            AnsiColors::BrightRed
+        ));
+        assert!(matches!(nearest_ansi([10, 10, 20]), AnsiColors::Black));
+
+        let painted = |depth| format!("{}", "x".color(to_dyn([226, 125, 95], depth)));
+        assert_eq!(
+            painted(ColorDepth::TrueColor),
+            "\u{1b}[38;2;226;125;95mx\u{1b}[39m"
+        );
+        assert_eq!(painted(ColorDepth::Ansi16), "\u{1b}[91mx\u{1b}[39m");
+    }
+}
@@ -0,0 +1,102 @@
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Compression formats that are decoded transparently before parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+impl Compression {
+    /// Detect the compression from the last extension of `path` (`data.csv.zst`).
+    pub fn from_path(path: &Path) -> Option<Compression> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            "bz2" => Some(Compression::Bzip2),
+            "xz" => Some(Compression::Xz),
+            _ => None,
+        }
+    }
+
+    /// Wrap `source` in the matching streaming decoder.
+    pub fn decoder<'a>(self, source: Box<dyn Read + 'a>) -> io::Result<Box<dyn Read + 'a>> {
+        let decoder: Box<dyn Read + 'a> = match self {
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(source)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(source)?),
+            Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(source)),
+            Compression::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(source)),
+        };
+        Ok(Box::new(BufReader::new(decoder)))
+    }
+}
+
+/// The path with any compression extension removed, so `data.tsv.gz` is
+/// treated like `data.tsv` when choosing the delimiter.
+pub fn strip_compression_extension(path: &Path) -> PathBuf {
+    match Compression::from_path(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const CSV: &str = "a,b\n1,2\n3,4\n";
+
+    fn round_trip(compression: Compression, compressed: Vec<u8>) {
+        let source: Box<dyn Read> = Box::new(io::Cursor::new(compressed));
+        let mut decoded = String::new();
+        compression
+            .decoder(source)
+            .unwrap()
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, CSV);
+    }
+
+    #[test]
+    fn detects_compression_from_extension() {
+        let detect = |p: &str| Compression::from_path(Path::new(p));
+        assert_eq!(detect("data.csv.gz"), Some(Compression::Gzip));
+        assert_eq!(detect("data.csv.zst"), Some(Compression::Zstd));
+        assert_eq!(detect("data.csv.BZ2"), Some(Compression::Bzip2));
+        assert_eq!(detect("data.csv.xz"), Some(Compression::Xz));
+        assert_eq!(detect("data.csv"), None);
+        assert_eq!(
+            strip_compression_extension(Path::new("data.tsv.zst")),
+            PathBuf::from("data.tsv")
+        );
+        assert_eq!(
+            strip_compression_extension(Path::new("data.tsv")),
+            PathBuf::from("data.tsv")
+        );
+    }
+
+    #[test]
+    fn decodes_all_formats() {
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(CSV.as_bytes()).unwrap();
+        round_trip(Compression::Gzip, gz.finish().unwrap());
+
+        round_trip(
+            Compression::Zstd,
+            zstd::stream::encode_all(CSV.as_bytes(), 0).unwrap(),
+        );
+
+        let mut bz = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+        bz.write_all(CSV.as_bytes()).unwrap();
+        round_trip(Compression::Bzip2, bz.finish().unwrap());
+
+        let mut xz = xz2::write::XzEncoder::new(Vec::new(), 6);
+        xz.write_all(CSV.as_bytes()).unwrap();
+        round_trip(Compression::Xz, xz.finish().unwrap());
+    }
+}
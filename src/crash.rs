@@ -0,0 +1,203 @@
+// A panic hook that turns a crash into a report that can be pasted into an
+// issue: the version, the platform, the flags and the shape of the record tv
+// was working on. The data itself is only shown with TV_CRASH_DATA=1.
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::panic::{self, PanicHookInfo};
+
+pub const ISSUES: &str = "https://github.com/alexhallam/tv/issues";
+
+/// How many bytes of the record TV_CRASH_DATA=1 shows.
+const DATA_BYTES: usize = 32;
+
+/// The record being worked on, kept small so noting one per row is cheap.
+#[derive(Default)]
+struct Record {
+    row: usize,
+    line: Option<u64>,
+    lengths: Vec<usize>,
+    head: String,
+}
+
+thread_local! {
+    static RECORD: RefCell<Option<Record>> = const { RefCell::new(None) };
+}
+
+/// Remember `fields`, row `row` of the data and line `line` of the file, as the
+/// record a crash would be about.
+pub fn note_record<'a>(row: usize, line: Option<u64>, fields: impl IntoIterator<Item = &'a str>) {
+    RECORD.with(|record| {
+        let mut record = record.borrow_mut();
+        let record = record.get_or_insert_with(Record::default);
+        record.row = row;
+        record.line = line;
+        record.lengths.clear();
+        record.head.clear();
+        for (i, field) in fields.into_iter().enumerate() {
+            record.lengths.push(field.len());
+            if i > 0 && record.head.len() < DATA_BYTES {
+                record.head.push(',');
+            }
+            let room = DATA_BYTES.saturating_sub(record.head.len());
+            record.head.push_str(prefix(field, room));
+        }
+    });
+}
+
+/// The start of `text`, at most `bytes` long and cut on a char boundary.
+fn prefix(text: &str, bytes: usize) -> &str {
+    let end = (0..=bytes.min(text.len()))
+        .rev()
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(0);
+    &text[..end]
+}
+
+/// Replace the default panic message by a report for the issue tracker.
+pub fn install() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        eprint!("{}", report(info));
+        // developers still get the usual message and backtrace
+        if std::env::var_os("RUST_BACKTRACE").is_some() {
+            default_hook(info);
+        }
+    }));
+}
+
+fn report(info: &PanicHookInfo) -> String {
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_default();
+    let show_data = std::env::var_os("TV_CRASH_DATA").is_some_and(|v| v == "1");
+
+    let mut out = String::new();
+    let _ = writeln!(out, "🤖 tv crashed, this is a bug. 🤖");
+    let _ = writeln!(
+        out,
+        "Please open an issue at {} with the lines below.",
+        ISSUES
+    );
+    let _ = writeln!(out);
+    let _ = writeln!(out, "version: tv {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        out,
+        "os:      {} {}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let _ = writeln!(out, "flags:   {}", flags(std::env::args().skip(1)));
+    let _ = write!(out, "panic:   {}", message);
+    if let Some(location) = info.location() {
+        let _ = write!(out, " at {}:{}", location.file(), location.line());
+    }
+    let _ = writeln!(out);
+    RECORD.with(|record| {
+        if let Some(record) = &*record.borrow() {
+            let _ = writeln!(out, "record:  {}", describe(record, show_data));
+        }
+    });
+    out
+}
+
+/// The flags of `args` without their values, which can be paths or data.
+fn flags(args: impl Iterator<Item = String>) -> String {
+    let mut flags = Vec::new();
+    let mut values = 0;
+    for arg in args {
+        if arg.starts_with("--") {
+            flags.push(arg.split('=').next().unwrap_or_default().to_string());
+        } else if arg.starts_with('-') && arg[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            // -g3 is -g with the value 3
+            flags.push(arg[..2].to_string());
+        } else {
+            values += 1;
+        }
+    }
+    match values {
+        0 => flags.join(" "),
+        1 => format!("{} (and 1 value, not shown)", flags.join(" ")),
+        n => format!("{} (and {} values, not shown)", flags.join(" "), n),
+    }
+    .trim_start()
+    .to_string()
+}
+
+fn describe(record: &Record, show_data: bool) -> String {
+    let mut out = format!("row {}", record.row);
+    if let Some(line) = record.line {
+        let _ = write!(out, " (line {})", line);
+    }
+    let lengths: Vec<String> = record.lengths.iter().map(|n| n.to_string()).collect();
+    let _ = write!(
+        out,
+        ", {} fields of {} bytes",
+        record.lengths.len(),
+        lengths.join(", ")
+    );
+    if show_data {
+        let _ = write!(out, ", starting {:?}", record.head);
+    } else {
+        let _ = write!(out, " (TV_CRASH_DATA=1 shows the data)");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn reports_flags_without_their_values() {
+        assert_eq!(
+            flags(args(&["-g3", "--delimiter=;", "-n", "20", "secret.csv"])),
+            "-g --delimiter -n (and 2 values, not shown)"
+        );
+        assert_eq!(flags(args(&["data.csv"])), "(and 1 value, not shown)");
+        assert_eq!(
+            flags(args(&["-a", "--color", "2"])),
+            "-a --color (and 1 value, not shown)"
+        );
+    }
+
+    #[test]
+    fn describes_a_record_by_its_lengths() {
+        note_record(3, Some(4), ["Ann", "", "Zoë"]);
+        RECORD.with(|record| {
+            let record = record.borrow();
+            let record = record.as_ref().unwrap();
+            assert_eq!(
+                describe(record, false),
+                "row 3 (line 4), 3 fields of 3, 0, 4 bytes (TV_CRASH_DATA=1 shows the data)"
+            );
+            assert!(!describe(record, false).contains("Ann"));
+            assert_eq!(
+                describe(record, true),
+                "row 3 (line 4), 3 fields of 3, 0, 4 bytes, starting \"Ann,,Zoë\""
+            );
+        });
+    }
+
+    #[test]
+    fn keeps_only_the_start_of_a_long_record() {
+        let long = format!("a{}", "é".repeat(1000));
+        note_record(1, None, [long.as_str(), "b"]);
+        RECORD.with(|record| {
+            let record = record.borrow();
+            let record = record.as_ref().unwrap();
+            assert_eq!(record.lengths, [2001, 1]);
+            // the next é would cross DATA_BYTES, only the comma fits
+            assert_eq!(record.head, format!("a{},", "é".repeat(15)));
+        });
+    }
+}
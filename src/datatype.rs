@@ -1,11 +1,13 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone as _, Utc};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::str::FromStr;
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
-use unicode_truncate::UnicodeTruncateStr;
 
 mod sigfig;
+pub use sigfig::{IntegerMode, MAX_SIGFIG};
 
 /// Represents the type of a value.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -31,10 +33,23 @@ pub fn is_logical(text: &str) -> bool {
     R.is_match(text)
 }
 
+/// The value of a logical, `None` if `text` is not one. The words of the locales
+/// of `opts` are logicals too.
+pub fn parse_logical(text: &str, opts: &FormatOptions) -> Option<bool> {
+    match text {
+        "true" | "t" | "TRUE" | "T" | "True" | "1" => Some(true),
+        "false" | "f" | "FALSE" | "F" | "False" | "0" => Some(false),
+        _ => locale_logical(text, &opts.locales),
+    }
+}
+
+/// An integer is an optional sign followed by one or more digits, with optional
+/// surrounding whitespace. Leading zeros are allowed ("007", "+0", "-0"), so every
+/// integer is also a number that `f64::from_str` accepts after trimming.
 pub fn is_integer(text: &str) -> bool {
     //let integer = "5";
     lazy_static! {
-        static ref R: Regex = Regex::new(r"^\s*([+-]?[1-9][0-9]*|0)\s*$").unwrap();
+        static ref R: Regex = Regex::new(r"^\s*[+-]?[0-9]+\s*$").unwrap();
     }
     R.is_match(text)
 }
@@ -43,6 +58,55 @@ pub fn is_number(text: &str) -> bool {
     is_integer(text) || is_double(text)
 }
 
+/// Hexadecimal (0x1A2B), binary (0b1010) and octal (0o755) integer literals.
+pub fn is_radix_integer(text: &str) -> bool {
+    lazy_static! {
+        static ref R: Regex =
+            Regex::new(r"^\s*[+-]?0(?:[xX][0-9a-fA-F]+|[bB][01]+|[oO][0-7]+)\s*$").unwrap();
+    }
+    R.is_match(text)
+}
+
+pub fn parse_radix_integer(text: &str) -> Option<i128> {
+    if !is_radix_integer(text) {
+        return None;
+    }
+    let text = text.trim();
+    let (neg, unsigned) = match text.as_bytes()[0] {
+        b'-' => (true, &text[1..]),
+        b'+' => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let radix = match unsigned.as_bytes()[1] {
+        b'x' | b'X' => 16,
+        b'b' | b'B' => 2,
+        _ => 8,
+    };
+    let value = i128::from_str_radix(&unsigned[2..], radix).ok()?;
+    Some(if neg { -value } else { value })
+}
+
+/// How radix integer literals are displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Radix {
+    /// As written in the file, right aligned.
+    #[default]
+    Keep,
+    /// Converted to decimal and formatted like any other number.
+    Dec,
+}
+
+impl FromStr for Radix {
+    type Err = String;
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        match src {
+            "keep" => Ok(Radix::Keep),
+            "dec" => Ok(Radix::Dec),
+            _ => Err(format!("expected keep or dec, got \"{}\"", src)),
+        }
+    }
+}
+
 pub fn is_negative_number(text: &str) -> bool {
     lazy_static! {
         static ref R: Regex = Regex::new(r"^\s*-[0-9]*.?[0-9]*\s*$").unwrap();
@@ -50,8 +114,72 @@ pub fn is_negative_number(text: &str) -> bool {
     R.is_match(text)
 }
 
+/// A decimal number: digits with an optional point, like "1.5", "1." or ".5",
+/// and an optional exponent, like "1e-3". inf, infinity and NaN, which f64
+/// also parses, are not numbers.
 pub fn is_double(text: &str) -> bool {
-    f64::from_str(text.trim()).is_ok()
+    lazy_static! {
+        static ref R: Regex =
+            Regex::new(r"^\s*[+-]?(?:[0-9]+\.?[0-9]*|\.[0-9]+)(?:[eE][+-]?[0-9]+)?\s*$").unwrap();
+    }
+    R.is_match(text)
+}
+
+/// A number written with a decimal comma, like "3,14" or "-0,5", optionally with
+/// dots between its thousands, like "1.234,5" or "1.234.567". Plain integers are
+/// left out, they read the same either way.
+pub fn is_decimal_comma(text: &str) -> bool {
+    lazy_static! {
+        static ref R: Regex =
+            Regex::new(r"^\s*[+-]?(?:[0-9]*,[0-9]+|[0-9]{1,3}(?:\.[0-9]{3})+(?:,[0-9]+)?)\s*$")
+                .unwrap();
+    }
+    R.is_match(text)
+}
+
+/// `text`, a number written with a decimal comma, with a decimal point instead, so
+/// "1.234,5" is "1234.5". `None` if `text` is not such a number.
+pub fn to_decimal_point(text: &str) -> Option<String> {
+    if !is_decimal_comma(text) {
+        return None;
+    }
+    Some(text.trim().replace('.', "").replacen(',', ".", 1))
+}
+
+/// A number with a percent sign after it, like "15.3%", "-2 %" or "100%".
+pub fn is_percent(text: &str) -> bool {
+    lazy_static! {
+        static ref R: Regex = Regex::new(r"^\s*[+-]?(?:[0-9]+\.?[0-9]*|\.[0-9]+)\s*%\s*$").unwrap();
+    }
+    R.is_match(text)
+}
+
+/// `text`, a proportion like "0.153", as a percentage like "15.3%". The decimal
+/// point is moved in the text, so no digits are made up. `None` if `text` is not
+/// a plain decimal number.
+pub fn to_percent(text: &str) -> Option<String> {
+    lazy_static! {
+        static ref R: Regex = Regex::new(r"^\s*([+-]?)([0-9]*)(?:\.([0-9]*))?\s*$").unwrap();
+    }
+    let caps = R.captures(text)?;
+    let sign = &caps[1];
+    let whole = &caps[2];
+    let fract = caps.get(3).map_or("", |m| m.as_str());
+    if whole.is_empty() && fract.is_empty() {
+        return None;
+    }
+    let fract = format!("{:0<2}", fract);
+    let (moved, fract) = fract.split_at(2);
+    let whole = format!("{}{}", whole, moved);
+    let whole = match whole.trim_start_matches('0') {
+        "" => "0",
+        whole => whole,
+    };
+    if fract.is_empty() {
+        Some(format!("{}{}%", sign, whole))
+    } else {
+        Some(format!("{}{}.{}%", sign, whole, fract))
+    }
 }
 
 pub fn is_time(text: &str) -> bool {
@@ -64,51 +192,377 @@ pub fn is_time(text: &str) -> bool {
     R.is_match(text)
 }
 
+/// A date like 2024-01-31, 01/31/2024, 31.01.2024, 20240131, Jan 31, 2024 or
+/// 31 January 2024. The compact 20240131 is an integer too, which it is typed as.
 pub fn is_date(text: &str) -> bool {
     lazy_static! {
         static ref R: Regex = Regex::new(r"\d{4}-\d{2}-\d{2}").unwrap();
     }
-    R.is_match(text)
+    R.is_match(text) || to_iso_date(text).is_some()
 }
 
-pub fn is_date_time(text: &str) -> bool {
-    //let datetime = "2020-10-09 11:59:37 UTC";
-    //https://stackoverflow.com/a/25873711
+/// `text`, a date in one of the forms of [`is_date`], as YYYY-MM-DD. Slashes are
+/// month first, dots day first. `None` if `text` is not a date that exists.
+pub fn to_iso_date(text: &str) -> Option<String> {
     lazy_static! {
-        static ref R: Regex =
-            Regex::new(r"^(?:[01][0-9]|2[0123]):(?:[012345][0-9]):(?:[012345][0-9])").unwrap();
+        static ref ISO: Regex = Regex::new(r"^(\d{4})-(\d{1,2})-(\d{1,2})$").unwrap();
+        static ref US: Regex = Regex::new(r"^(\d{1,2})/(\d{1,2})/(\d{4})$").unwrap();
+        static ref DOTS: Regex = Regex::new(r"^(\d{1,2})\.(\d{1,2})\.(\d{4})$").unwrap();
+        static ref COMPACT: Regex = Regex::new(r"^(\d{4})(\d{2})(\d{2})$").unwrap();
+        static ref MONTH_FIRST: Regex =
+            Regex::new(r"^([A-Za-z]+)\.? (\d{1,2}),? (\d{4})$").unwrap();
+        static ref DAY_FIRST: Regex = Regex::new(r"^(\d{1,2}) ([A-Za-z]+)\.?,? (\d{4})$").unwrap();
     }
-    R.is_match(text)
+    let text = text.trim();
+    let number = |caps: &regex::Captures, i: usize| caps[i].parse::<u32>().ok();
+    let (year, month, day) = if let Some(caps) = ISO.captures(text) {
+        (number(&caps, 1)?, number(&caps, 2)?, number(&caps, 3)?)
+    } else if let Some(caps) = US.captures(text) {
+        (number(&caps, 3)?, number(&caps, 1)?, number(&caps, 2)?)
+    } else if let Some(caps) = DOTS.captures(text) {
+        (number(&caps, 3)?, number(&caps, 2)?, number(&caps, 1)?)
+    } else if let Some(caps) = COMPACT.captures(text) {
+        (number(&caps, 1)?, number(&caps, 2)?, number(&caps, 3)?)
+    } else if let Some(caps) = MONTH_FIRST.captures(text) {
+        (
+            number(&caps, 3)?,
+            month_number(&caps[1])?,
+            number(&caps, 2)?,
+        )
+    } else if let Some(caps) = DAY_FIRST.captures(text) {
+        (
+            number(&caps, 3)?,
+            month_number(&caps[2])?,
+            number(&caps, 1)?,
+        )
+    } else {
+        return None;
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if day == 0 || day > days {
+        return None;
+    }
+    Some(format!("{:04}-{:02}-{:02}", year, month, day))
 }
 
-pub fn is_na(text: &str) -> bool {
+/// The number of the English month `name`, in full or shortened like Jan or Sept.
+fn month_number(name: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    let name = name.to_lowercase();
+    if name.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|month| month.starts_with(&name))
+        .map(|i| i as u32 + 1)
+}
+
+/// An ISO 8601 date-time like 2020-10-09T11:59:37Z, 2020-10-09 11:59:37.250+02:00
+/// or 2020-10-09 11:59, with or without a zone.
+pub fn is_date_time(text: &str) -> bool {
+    parse_date_time(text).is_some()
+}
+
+/// The time of a date-time and its offset from UTC, if it has a zone.
+fn parse_date_time(text: &str) -> Option<(NaiveDateTime, Option<FixedOffset>)> {
     lazy_static! {
         static ref R: Regex = Regex::new(
-            r"^$|^(?:N(?:(?:(?:one|AN|a[Nn]|/A)|[Aa])|ull)|n(?:ull|an?|/a?)|(?:missing))$"
+            r"^\s*(\d{4}-\d{2}-\d{2})[Tt ](\d{2}:\d{2}(?::\d{2})?)(?:[.,]\d+)?\s*(Z|z|UTC|[+-]\d{2}(?::?\d{2})?)?\s*$"
         )
         .unwrap();
     }
-    R.is_match(text)
+    let caps = R.captures(text)?;
+    let time = match caps[2].len() {
+        5 => format!("{}:00", &caps[2]),
+        _ => caps[2].to_string(),
+    };
+    let time =
+        NaiveDateTime::parse_from_str(&format!("{} {}", &caps[1], time), "%Y-%m-%d %H:%M:%S")
+            .ok()?;
+    let offset = match caps.get(3).map(|zone| zone.as_str()) {
+        None => None,
+        Some(zone) => Some(parse_offset(zone)?),
+    };
+    Some((time, offset))
 }
 
-pub fn is_na_string_padded(text: &str) -> bool {
+/// `zone` like Z, UTC, +05:30, -0800 or +01 as an offset from UTC.
+fn parse_offset(zone: &str) -> Option<FixedOffset> {
+    if matches!(zone, "Z" | "z") || zone.eq_ignore_ascii_case("utc") {
+        return FixedOffset::east_opt(0);
+    }
     lazy_static! {
-        static ref R: Regex = Regex::new(
-            r"^$|(^|\s)(?:N(?:(?:(?:AN|a[Nn]|/A)|[Aa])|ull)|n(?:ull|an?|/a?)|(?:missing))\s*$"
-        )
-        .unwrap();
+        static ref R: Regex = Regex::new(r"^([+-])(\d{2})(?::?(\d{2}))?$").unwrap();
+    }
+    let caps = R.captures(zone)?;
+    let hours: i32 = caps[2].parse().ok()?;
+    let minutes: i32 = caps.get(3).map_or(Some(0), |m| m.as_str().parse().ok())?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    let seconds = (hours * 60 + minutes) * 60;
+    FixedOffset::east_opt(if &caps[1] == "-" { -seconds } else { seconds })
+}
+
+/// The zone date-times are shown in, see [`to_date_time`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeZone {
+    /// The zone of the computer tv runs on.
+    Local,
+    Utc,
+    /// A fixed offset from UTC, like +05:30.
+    Offset(FixedOffset),
+}
+
+impl FromStr for TimeZone {
+    type Err = String;
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        if src.eq_ignore_ascii_case("local") {
+            return Ok(TimeZone::Local);
+        }
+        match parse_offset(src) {
+            Some(offset) if offset.local_minus_utc() == 0 => Ok(TimeZone::Utc),
+            Some(offset) => Ok(TimeZone::Offset(offset)),
+            None => Err(format!(
+                "expected local, UTC or an offset like +05:30, got \"{}\"",
+                src
+            )),
+        }
+    }
+}
+
+/// `text`, a date-time, to the second like 2020-10-09 11:59:37Z. Date-times with a
+/// zone are shown in `tz` when it is given, in their own zone otherwise. Those
+/// without one have nothing to convert from and are only cut to the second.
+/// `None` if `text` is not a date-time.
+pub fn to_date_time(text: &str, tz: Option<TimeZone>) -> Option<String> {
+    let (time, offset) = parse_date_time(text)?;
+    let Some(offset) = offset else {
+        return Some(time.format(SECONDS).to_string());
+    };
+    Some(show_date_time(
+        offset.from_local_datetime(&time).single()?,
+        tz,
+    ))
+}
+
+/// The time of `text`, a Unix timestamp: an integer of up to 10 digits is seconds,
+/// one of 13 digits milliseconds. Other lengths are not timestamps.
+fn parse_epoch(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let n: i64 = text.parse().ok()?;
+    match digits.len() {
+        1..=10 => DateTime::from_timestamp(n, 0),
+        13 => DateTime::from_timestamp_millis(n),
+        _ => None,
+    }
+}
+
+/// `text`, a Unix timestamp of up to 10 digits, seconds, or 13, milliseconds, as
+/// a date-time like 2020-10-09 11:59:37Z, in `tz` when given. `None` if `text` is
+/// not such a timestamp.
+pub fn from_epoch(text: &str, tz: Option<TimeZone>) -> Option<String> {
+    let time = parse_epoch(text)?;
+    Some(show_date_time(time.fixed_offset(), tz))
+}
+
+/// Does the column `name` with the values `column` hold Unix timestamps? Its name
+/// ends in _ts or _time and its values are timestamps [`from_epoch`] can show.
+pub fn is_epoch_column(name: &str, column: &[&str], opts: &FormatOptions) -> bool {
+    let name = name.trim().to_lowercase();
+    let mut values = column
+        .iter()
+        .map(|text| text.trim())
+        .filter(|text| !is_missing(text, opts))
+        .peekable();
+    (name.ends_with("_ts") || name.ends_with("_time"))
+        && values.peek().is_some()
+        && values.all(|text| parse_epoch(text).is_some())
+}
+
+const SECONDS: &str = "%Y-%m-%d %H:%M:%S";
+
+fn show_date_time(time: DateTime<FixedOffset>, tz: Option<TimeZone>) -> String {
+    let time = match tz {
+        None => time,
+        Some(TimeZone::Utc) => time.with_timezone(&Utc).fixed_offset(),
+        Some(TimeZone::Offset(offset)) => time.with_timezone(&offset),
+        Some(TimeZone::Local) => time.with_timezone(&Local).fixed_offset(),
+    };
+    if time.offset().local_minus_utc() == 0 {
+        format!("{}Z", time.format(SECONDS))
+    } else {
+        time.format("%Y-%m-%d %H:%M:%S%:z").to_string()
+    }
+}
+
+/// Is `text` one of the [`NaValues::Standard`] missing values? [`is_missing`]
+/// uses the ones of the options.
+pub fn is_na(text: &str) -> bool {
+    NaValues::Standard.matches(text)
+}
+
+/// Is `text` one of the missing values of `opts`, whether or not they are shown
+/// as such?
+fn is_na_value(text: &str, opts: &FormatOptions) -> bool {
+    opts.na_values.matches(text)
+        || opts
+            .locales
+            .iter()
+            .any(|locale| locale.na_words().contains(&text))
+}
+
+/// Which strings are missing values.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum NaValues {
+    /// Empty fields, NA and NULL.
+    Strict,
+    /// Also the NA-like strings of R, pandas and SQL exports: NaN, null, None,
+    /// n/a, missing and the like.
+    #[default]
+    Standard,
+    /// Also the placeholders of hand-made sheets: -, ?, #N/A, nil and the like.
+    Loose,
+    /// Exactly these strings.
+    List(Vec<String>),
+}
+
+impl FromStr for NaValues {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "strict" => Ok(NaValues::Strict),
+            "standard" => Ok(NaValues::Standard),
+            "loose" => Ok(NaValues::Loose),
+            _ => Err(format!(
+                "unknown NA preset \"{}\", expected strict, standard or loose",
+                s
+            )),
+        }
+    }
+}
+
+impl NaValues {
+    pub fn matches(&self, text: &str) -> bool {
+        lazy_static! {
+            static ref R: Regex = Regex::new(
+                r"^$|^(?:N(?:(?:(?:one|AN|a[Nn]|/A)|[Aa])|ull)|n(?:ull|an?|/a?)|(?:missing))$"
+            )
+            .unwrap();
+        }
+        const LOOSE: &[&str] = &[
+            "-", "--", "?", ".", "#N/A", "#NA", "N.A.", "n.a.", "nil", "NIL", "none", "NONE",
+        ];
+        match self {
+            NaValues::Strict => matches!(text, "" | "NA" | "NULL"),
+            NaValues::Standard => R.is_match(text),
+            NaValues::Loose => R.is_match(text) || LOOSE.contains(&text),
+            NaValues::List(values) => values.iter().any(|value| value == text),
+        }
+    }
+}
+
+/// The words spreadsheets exported in another language use for missing values
+/// and logicals, like "k.A." and WAHR in German. They are recognized by
+/// [`is_missing`] and [`parse_logical`] for the locales of the options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    De,
+    Es,
+    Fr,
+    It,
+    Pt,
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "de" => Ok(Locale::De),
+            "es" => Ok(Locale::Es),
+            "fr" => Ok(Locale::Fr),
+            "it" => Ok(Locale::It),
+            "pt" => Ok(Locale::Pt),
+            _ => Err(format!(
+                "unknown locale \"{}\", expected de, es, fr, it or pt",
+                s
+            )),
+        }
     }
-    R.is_match(text)
+}
+
+impl Locale {
+    /// The words for a missing value, with the error value of the spreadsheet.
+    pub fn na_words(self) -> &'static [&'static str] {
+        match self {
+            Locale::De => &["k.A.", "k. A.", "n.v.", "#NV"],
+            Locale::Es => &["N/D", "n/d", "s/d", "S/D", "#N/D"],
+            Locale::Fr => &["N/D", "n/d", "n.d.", "#N/A"],
+            Locale::It => &["N/D", "n/d", "n.d.", "#N/D"],
+            Locale::Pt => &["N/D", "n/d", "s/d", "S/D", "#N/D"],
+        }
+    }
+
+    /// The words for true and false, matched ignoring case.
+    pub fn logical_words(self) -> (&'static str, &'static str) {
+        match self {
+            Locale::De => ("WAHR", "FALSCH"),
+            Locale::Es => ("VERDADERO", "FALSO"),
+            Locale::Fr => ("VRAI", "FAUX"),
+            Locale::It => ("VERO", "FALSO"),
+            Locale::Pt => ("VERDADEIRO", "FALSO"),
+        }
+    }
+}
+
+fn locale_logical(text: &str, locales: &[Locale]) -> Option<bool> {
+    locales.iter().find_map(|locale| {
+        let (yes, no) = locale.logical_words();
+        if text.eq_ignore_ascii_case(yes) {
+            Some(true)
+        } else if text.eq_ignore_ascii_case(no) {
+            Some(false)
+        } else {
+            None
+        }
+    })
 }
 
 // utilities
 
-pub fn infer_type_from_string(text: &str) -> ValueType {
+pub fn infer_type_from_string(text: &str, opts: &FormatOptions) -> ValueType {
     if is_time(text) {
         ValueType::Time
-    } else if is_logical(text) {
+    } else if is_logical(text) || locale_logical(text, &opts.locales).is_some() {
         ValueType::Boolean
-    } else if is_integer(text) {
+    } else if is_integer(text) || is_radix_integer(text) {
         ValueType::Integer
     } else if is_date_time(text) {
         ValueType::DateTime
@@ -116,36 +570,238 @@ pub fn infer_type_from_string(text: &str) -> ValueType {
         ValueType::Date
     } else if is_double(text) {
         ValueType::Double
-    } else if text.is_empty() | is_na(text) {
+    } else if text.is_empty() || is_missing(text, opts) {
         ValueType::Na
     } else {
         ValueType::Character
     }
 }
 
-pub fn format_strings(
-    vec_col: &[&str],
-    lower_column_width: usize,
-    upper_column_width: usize,
-    sigfig: i64,
-) -> Vec<String> {
-    let ellipsis = '\u{2026}';
+/// Options shared by the column formatters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// The lower (minimum) width of columns.
+    pub lower_column_width: usize,
+    /// The upper (maximum) width of columns. Longer values are truncated with an ellipsis.
+    pub upper_column_width: usize,
+    /// Significant figures for numbers.
+    pub sigfig: i64,
+    pub integer_mode: IntegerMode,
+    pub radix: Radix,
+    /// Treat NA-like strings ("NA", "null", "n/a", ...) as missing. When off only
+    /// empty fields, the real nulls of a text file, are missing.
+    pub na_strings: bool,
+    /// Which strings are missing values.
+    pub na_values: NaValues,
+    /// The languages whose words for missing values and logicals are recognized.
+    pub locales: Vec<Locale>,
+    /// Width of the row number gutter left of the table. 0 removes the gutter.
+    pub gutter_width: usize,
+    /// Print a blank line before the output.
+    pub leading_newline: bool,
+    /// Show numbers with a decimal comma, 3,14 rather than 3.14.
+    pub decimal_comma: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            lower_column_width: 2,
+            upper_column_width: 20,
+            sigfig: 3,
+            integer_mode: IntegerMode::Full,
+            radix: Radix::Keep,
+            na_strings: true,
+            na_values: NaValues::Standard,
+            locales: Vec::new(),
+            gutter_width: 6,
+            leading_newline: true,
+            decimal_comma: false,
+        }
+    }
+}
 
-    let strings_and_fracts: Vec<(String, usize, usize)> = vec_col
+/// Is the raw cell value missing under `opts`?
+pub fn is_missing(text: &str, opts: &FormatOptions) -> bool {
+    if opts.na_strings {
+        is_na_value(text, opts)
+    } else {
+        text.is_empty()
+    }
+}
+
+/// What a shown cell holds, which picks its color and alignment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellRole {
+    /// Text, dates, logicals and the header.
+    Text,
+    /// A number of zero or more.
+    Number,
+    /// A number below zero.
+    Negative,
+    /// A missing value.
+    Missing,
+}
+
+/// The role of each cell of a column, the header first, from its raw values and
+/// the formatted ones of [`format_strings`] or [`format_column`]. Missing is
+/// decided on the raw value, so a literal "NA" kept with `na_strings` off is
+/// text, and numbers on the formatted one, as they are shown.
+pub fn cell_roles(raw: &[&str], shown: &[String], opts: &FormatOptions) -> Vec<CellRole> {
+    raw.iter()
+        .zip(shown)
+        .enumerate()
+        .map(|(i, (raw, shown))| {
+            let shown = match opts.decimal_comma {
+                true => shown.trim().replacen(',', ".", 1),
+                false => shown.trim().to_string(),
+            };
+            // a percentage is a number with a sign after it
+            let shown = shown.strip_suffix('%').unwrap_or(&shown);
+            if i == 0 {
+                CellRole::Text
+            } else if is_missing(raw, opts) {
+                CellRole::Missing
+            } else if !is_double(shown) {
+                CellRole::Text
+            } else if shown.starts_with('-') && is_negative_number(shown) {
+                CellRole::Negative
+            } else {
+                CellRole::Number
+            }
+        })
+        .collect()
+}
+
+/// The longest start of `text` at most `width` columns wide, and its width. Text is
+/// only cut between grapheme clusters, so an emoji sequence like 👨‍👩‍👧, a flag or a
+/// letter with a combining accent is kept whole or left out.
+pub fn truncate_graphemes(text: &str, width: usize) -> (&str, usize) {
+    let mut end = 0;
+    let mut used = 0;
+    for (idx, grapheme) in text.grapheme_indices(true) {
+        let grapheme_width = grapheme.width();
+        if used + grapheme_width > width {
+            break;
+        }
+        end = idx + grapheme.len();
+        used += grapheme_width;
+    }
+    (&text[..end], used)
+}
+
+/// The narrowest a column with `header` can be once formatted, with the space
+/// after it, found without formatting its values. Columns that can not be shown
+/// even at this width need not be formatted.
+pub fn min_column_width(header: &str, opts: &FormatOptions) -> usize {
+    header
+        .width()
+        .clamp(opts.lower_column_width, opts.upper_column_width)
+        + 1
+}
+
+/// Format a column whose type is inferred cell by cell. The first value is the header.
+pub fn format_strings(vec_col: &[&str], opts: &FormatOptions) -> Vec<String> {
+    let strings: Vec<String> = vec_col
+        .iter()
+        .map(|&string| {
+            if is_missing(string, opts) {
+                missing_text(opts)
+            } else {
+                string.to_string()
+            }
+        })
+        .map(|string| format_if_radix(&string, opts.radix))
+        .map(|string| format_if_num(&string, opts.sigfig, opts.integer_mode))
+        .collect();
+    align_and_pad(strings, opts, true)
+}
+
+/// Format a column whose type is already known, e.g. from a file schema, skipping
+/// per-cell inference. The first value is the header and is only padded.
+///
+/// Cells of numeric columns that are not numbers are shown as missing, and cells of
+/// other columns are never number formatted, so a character column of "1.50" stays
+/// "1.50".
+pub fn format_column(values: &[&str], dtype: ValueType, opts: &FormatOptions) -> Vec<String> {
+    let numeric = matches!(dtype, ValueType::Integer | ValueType::Double);
+    let strings: Vec<String> = values
         .iter()
-        .map(|&string| format_if_na(string))
-        .map(|string| format_if_num(&string, sigfig))
+        .enumerate()
+        .map(|(i, &value)| {
+            if i == 0 {
+                value.to_string()
+            } else if dtype == ValueType::Na || is_missing(value, opts) {
+                missing_text(opts)
+            } else if numeric {
+                let value = format_if_radix(value, opts.radix);
+                if is_double(&value) {
+                    format_if_num(value.trim(), opts.sigfig, opts.integer_mode)
+                } else if is_radix_integer(&value) {
+                    value.trim().to_string()
+                } else {
+                    missing_text(opts)
+                }
+            } else {
+                value.to_string()
+            }
+        })
+        .collect();
+    align_and_pad(strings, opts, numeric)
+}
+
+/// Show `annotation` after the header of a column formatted by [`format_strings`]
+/// or [`format_column`], like the ▲ of a sorted column. `name` is the header as
+/// read. The column is widened for the annotation up to the upper column width,
+/// past which the name is cut short so the annotation is always shown whole.
+pub fn annotate_header(column: &mut [String], name: &str, annotation: &str, opts: &FormatOptions) {
+    let Some(header) = column.first() else {
+        return;
+    };
+    // every cell ends in the space between columns
+    let width = header.width().saturating_sub(1);
+    let annotated_width = name.width() + 1 + annotation.width();
+    let wanted = annotated_width.min(opts.upper_column_width).max(width);
+    if wanted > width {
+        let spacer = " ".repeat(wanted - width);
+        column.iter_mut().for_each(|cell| cell.push_str(&spacer));
+    }
+    let text = if annotated_width <= wanted {
+        format!("{} {}", name, annotation)
+    } else {
+        let room = wanted.saturating_sub(annotation.width() + 2);
+        let (cut, _) = truncate_graphemes(name, room);
+        format!("{}\u{2026} {}", cut, annotation)
+    };
+    // only an annotation wider than the column itself is cut
+    let (text, text_width) = truncate_graphemes(&text, wanted);
+    column[0] = format!("{}{}", text, " ".repeat(wanted - text_width + 1));
+}
+
+// Line up numbers on their decimal point, then pad (or truncate) every string to the
+// column width. `align_numbers` is off for columns known not to hold numbers.
+fn align_and_pad(strings: Vec<String>, opts: &FormatOptions, align_numbers: bool) -> Vec<String> {
+    let ellipsis = '\u{2026}';
+    // markers that IntegerMode::Rounded and IntegerMode::Compact put after a
+    // number, and the sign of a percentage
+    let markers: &[char] = match opts.integer_mode {
+        IntegerMode::Full => &['%'],
+        IntegerMode::Rounded | IntegerMode::Compact => &['~', 'K', 'M', 'B', 'T', '%'],
+    };
+    let split = |string: &str| {
+        if align_numbers {
+            decimal_split(string, markers)
+        } else {
+            None
+        }
+    };
+    let is_radix = |string: &str| align_numbers && is_radix_integer(string);
+
+    let strings_and_fracts: Vec<(String, usize, usize)> = strings
+        .into_iter()
         .map(|string| {
             // the string, and the length of its fractional digits if any
-            let (lhs, rhs) = if is_double(&string) {
-                let mut split = string.split('.');
-                (
-                    split.next().map(|lhs| lhs.len()).unwrap_or_default(),
-                    split.next().map(|rhs| rhs.len()).unwrap_or_default(),
-                )
-            } else {
-                (0, 0)
-            };
+            let (lhs, rhs) = split(&string).unwrap_or((0, 0));
             (string, lhs, rhs)
         })
         .collect();
@@ -161,10 +817,19 @@ pub fn format_strings(
         .max()
         .unwrap_or_default();
 
+    // radix literals kept as written are right aligned
+    let max_radix: usize = strings_and_fracts
+        .iter()
+        .filter(|(string, _, _)| is_radix(string))
+        .map(|(string, _, _)| string.len())
+        .max()
+        .unwrap_or_default();
+
     let strings_and_widths: Vec<(String, usize)> = strings_and_fracts
         .into_iter()
         .map(|(mut string, whole, fract)| {
-            if max_fract > 0 && is_double(&string) {
+            let is_number = split(&string).is_some();
+            if max_fract > 0 && is_number {
                 if whole < max_whole {
                     let mut s = String::new();
                     s.push_str(&" ".repeat(max_whole - whole));
@@ -173,7 +838,7 @@ pub fn format_strings(
                 }
 
                 string.push_str(&" ".repeat(max_fract - fract));
-            } else if max_fract > 0 && is_na(&string) {
+            } else if max_fract > 0 && opts.na_strings && is_na(&string) {
                 if 2 < max_whole {
                     let mut s = String::new();
                     s.push_str(&" ".repeat(max_whole - 2));
@@ -182,6 +847,11 @@ pub fn format_strings(
                 }
 
                 string.push_str(&" ".repeat(max_fract - fract));
+            } else if max_radix > 0 && is_radix(&string) {
+                string = format!("{:>w$}", string, w = max_radix);
+            }
+            if opts.decimal_comma && is_number {
+                string = string.replacen('.', ",", 1);
             }
             let len = UnicodeWidthStr::width(string.as_str());
             // the string and its length
@@ -194,16 +864,17 @@ pub fn format_strings(
         .map(|(_, width)| *width)
         .max()
         .unwrap_or_default()
-        .clamp(lower_column_width, upper_column_width);
+        .clamp(opts.lower_column_width, opts.upper_column_width);
 
     strings_and_widths
         .into_iter()
         .map(|(string, len)| {
             if len > max_width {
-                let (rv, _) = string.unicode_truncate(max_width - 1);
-                let spacer: &str = " ";
+                let (rv, rv_width) = truncate_graphemes(&string, max_width - 1);
+                // a wide character cut off leaves a gap, padded so the column lines up
+                let spacer = " ".repeat(max_width - rv_width);
                 let string_and_ellipses = [rv.to_string(), ellipsis.to_string()].join("");
-                [string_and_ellipses, spacer.to_string()].join("")
+                [string_and_ellipses, spacer].join("")
             } else {
                 let add_space = max_width - len + 1;
                 let borrowed_string: &str = &" ".repeat(add_space);
@@ -213,6 +884,31 @@ pub fn format_strings(
         .collect()
 }
 
+// The lengths of a number's whole and fractional parts, used for decimal alignment.
+// A trailing marker ("12300~", "12.3K") counts towards the fractional part.
+fn decimal_split(text: &str, markers: &[char]) -> Option<(usize, usize)> {
+    let number = text.trim_end_matches(markers);
+    if !is_double(number) {
+        return None;
+    }
+    let marker_len = text.len() - number.len();
+    let mut split = number.split('.');
+    Some((
+        split.next().map(|lhs| lhs.len()).unwrap_or_default(),
+        split.next().map(|rhs| rhs.len()).unwrap_or_default() + marker_len,
+    ))
+}
+
+/// How a missing cell is shown: NA, or left blank when `na_strings` is off, so
+/// that it is not mistaken for a literal "NA" shown as written.
+fn missing_text(opts: &FormatOptions) -> String {
+    if opts.na_strings {
+        format_if_na("")
+    } else {
+        String::new()
+    }
+}
+
 pub fn format_if_na(text: &str) -> String {
     // todo add repeat strings for NA
     let missing_string_value = "NA";
@@ -224,19 +920,50 @@ pub fn format_if_na(text: &str) -> String {
     string.to_string()
 }
 
-pub fn format_if_num(text: &str, sigfig: i64) -> String {
-    if let Ok(val) = text.parse::<f64>() {
-        sigfig::DecimalSplits { val, sigfig }.final_string()
+pub fn format_if_radix(text: &str, radix: Radix) -> String {
+    match (radix, parse_radix_integer(text)) {
+        (Radix::Dec, Some(value)) => value.to_string(),
+        _ => text.to_string(),
+    }
+}
+
+pub fn format_if_num(text: &str, sigfig: i64, integer_mode: IntegerMode) -> String {
+    // inf, NaN and numbers too large for an f64, like 1e400, are shown as written
+    let val = text.parse::<f64>().ok().filter(|val| val.is_finite());
+    if let Some(val) = val.filter(|_| is_double(text)) {
+        sigfig::DecimalSplits {
+            val,
+            sigfig,
+            integer_mode,
+        }
+        .final_string()
+    } else if is_percent(text) {
+        let number = text.trim().trim_end_matches('%').trim_end();
+        format!("{}%", format_if_num(number, sigfig, integer_mode))
     } else {
         text.to_string()
     }
 }
 
-pub fn get_col_data_type(col: &[&str]) -> ValueType {
+/// Whether `shown`, a formatted value, is a rounded `raw` number. Compact values
+/// like 12.3K and values cut with an ellipsis count as rounded. `shown` may have
+/// a decimal comma.
+pub fn is_rounded(raw: &str, shown: &str) -> bool {
+    if !is_number(raw) {
+        return false;
+    }
+    let raw: f64 = raw.trim().parse().unwrap_or_default();
+    match shown.trim().replacen(',', ".", 1).parse::<f64>() {
+        Ok(shown) => shown != raw,
+        Err(_) => true,
+    }
+}
+
+pub fn get_col_data_type(col: &[&str], opts: &FormatOptions) -> ValueType {
     // counts the frequency of the datatypes in the column
     // returns the most frequent while ignoring NA values.
     col.iter()
-        .map(|x| infer_type_from_string(x))
+        .map(|x| infer_type_from_string(x, opts))
         .filter(|x| !matches!(x, &ValueType::Na))
         .group_by(|&x| x)
         .into_iter()
@@ -246,14 +973,106 @@ pub fn get_col_data_type(col: &[&str]) -> ValueType {
         .unwrap()
 }
 
-pub fn parse_delimiter(src: &str) -> Result<u8, String> {
-    let bytes = src.as_bytes();
-    match *bytes {
-        [del] => Ok(del),
-        [b'\\', b't'] => Ok(b'\t'),
+/// Parse a delimiter given on the command line. It may be one of the names `comma`,
+/// `tab`, `pipe`, `semicolon` and `space`, or else several bytes long (`||`) and
+/// may use the escapes `\t`, `\\` and `\xNN` (`\x01`).
+pub fn parse_delimiter(src: &str) -> Result<Box<[u8]>, String> {
+    let named = match src {
+        "comma" => Some(b','),
+        "tab" => Some(b'\t'),
+        "pipe" => Some(b'|'),
+        "semicolon" => Some(b';'),
+        "space" => Some(b' '),
+        _ => None,
+    };
+    if let Some(delimiter) = named {
+        return Ok(Box::new([delimiter]));
+    }
+    let mut bytes = Vec::new();
+    let mut rest = src.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        match rest {
+            [b't', tail @ ..] => {
+                bytes.push(b'\t');
+                rest = tail;
+            }
+            [b'\\', tail @ ..] => {
+                bytes.push(b'\\');
+                rest = tail;
+            }
+            [b'x', hi, lo, tail @ ..] if hi.is_ascii_hexdigit() && lo.is_ascii_hexdigit() => {
+                let hex = std::str::from_utf8(&rest[1..3]).unwrap();
+                bytes.push(u8::from_str_radix(hex, 16).unwrap());
+                rest = tail;
+            }
+            _ => {
+                return Err(format!(
+                    "unknown escape in delimiter \"{}\", expected \\t, \\\\ or \\xNN",
+                    src
+                ))
+            }
+        }
+    }
+    if bytes.is_empty() {
+        return Err("expected a delimiter, got an empty string".to_string());
+    }
+    if bytes.contains(&b'\n') {
+        return Err(format!("a newline can not be a delimiter (\"{}\")", src));
+    }
+    Ok(bytes.into_boxed_slice())
+}
+
+/// The csv reader of tv over `source`. The header is read as a row like the others,
+/// rows may differ in length when `flexible` and lines starting with `comment` are
+/// skipped.
+pub fn csv_reader<R: std::io::Read>(
+    source: R,
+    delimiter: u8,
+    flexible: bool,
+    comment: Option<u8>,
+) -> csv::Reader<R> {
+    csv::ReaderBuilder::new()
+        .flexible(flexible)
+        .has_headers(false)
+        .delimiter(delimiter)
+        .comment(comment)
+        .from_reader(source)
+}
+
+/// Parse the number of significant figures given on the command line, 1 to 15.
+pub fn parse_sigfig(src: &str) -> Result<i64, String> {
+    match src.parse::<i64>() {
+        Ok(sigfig) if (1..=sigfig::MAX_SIGFIG).contains(&sigfig) => Ok(sigfig),
+        _ => Err(format!(
+            "expected significant figures from 1 to {}, got \"{}\"",
+            sigfig::MAX_SIGFIG,
+            src
+        )),
+    }
+}
+
+/// Parse a percentage given on the command line, like `50` or `50%`.
+pub fn parse_percent(src: &str) -> Result<f64, String> {
+    match src.trim_end_matches('%').parse::<f64>() {
+        Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(percent),
+        _ => Err(format!(
+            "expected a percentage from 0 to 100, got \"{}\"",
+            src
+        )),
+    }
+}
+
+/// Parse the comment character given on the command line or in tv.toml.
+pub fn parse_comment(src: &str) -> Result<u8, String> {
+    match *src.as_bytes() {
+        [comment] if comment != b'\n' => Ok(comment),
         _ => Err(format!(
-            "expected one byte as delimiter, got {} bytes (\"{}\")",
-            bytes.len(),
+            "expected one byte as comment character, got \"{}\"",
             src
         )),
     }
@@ -261,35 +1080,652 @@ pub fn parse_delimiter(src: &str) -> Result<u8, String> {
 
 #[cfg(test)]
 mod tests {
-    use crate::datatype::parse_delimiter;
+    use crate::datatype::{
+        annotate_header, cell_roles, min_column_width, truncate_graphemes, CellRole,
+    };
+    use crate::datatype::{
+        format_column, format_strings, infer_type_from_string, is_double, is_integer, is_missing,
+        is_number, is_radix_integer, parse_comment, parse_delimiter, parse_percent,
+        parse_radix_integer, parse_sigfig, to_decimal_point, FormatOptions, Radix, ValueType,
+    };
+    use crate::datatype::{format_if_num, IntegerMode};
+    use crate::datatype::{from_epoch, is_epoch_column};
+    use crate::datatype::{is_date, is_date_time, to_date_time, to_iso_date, TimeZone};
+    use crate::datatype::{is_logical, is_na, is_rounded, parse_logical, Locale};
+    use crate::datatype::{is_percent, to_percent, NaValues};
+    use proptest::prelude::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn integers_with_signs_and_leading_zeros() {
+        for text in ["0", "+0", "-0", "007", "+5", "-12", "  42  ", "000"] {
+            assert!(is_integer(text), "{:?} should be an integer", text);
+        }
+        for text in ["", "+", "-", "1.0", "1e3", "0x1A", "1 2", "++1", "one"] {
+            assert!(!is_integer(text), "{:?} should not be an integer", text);
+        }
+    }
+
+    #[test]
+    fn only_finite_numbers_are_formatted() {
+        for text in ["Infinity", "inf", "-inf", "NaN", "nan", "1e400", "-1e400"] {
+            assert_eq!(format_if_num(text, 3, IntegerMode::Full), text);
+        }
+        assert_eq!(format_if_num("1e3", 3, IntegerMode::Full), "1000");
+        let opts = FormatOptions {
+            na_strings: false,
+            ..FormatOptions::default()
+        };
+        let shown = format_strings(&["x", "NaN", "1e400", "1.5"], &opts);
+        let shown: Vec<&str> = shown.iter().map(|s| s.trim()).collect();
+        assert_eq!(shown, ["x", "NaN", "1e400", "1.5"]);
+    }
+
+    #[test]
+    fn doubles_with_points_and_exponents() {
+        for text in [
+            "1.5",
+            "-1.5",
+            "+.5",
+            "1.",
+            "1e5",
+            "-1.5E-3",
+            " 2.0e+10 ",
+            "007.50",
+        ] {
+            assert!(is_double(text), "{:?} should be a double", text);
+        }
+        for text in [
+            "", ".", "-.", "e5", ".e5", "1e", "1e+", "1.2.3", "1,5", "0x1A",
+        ] {
+            assert!(!is_double(text), "{:?} should not be a double", text);
+        }
+        for text in ["inf", "-inf", "+infinity", "Infinity", "NaN", "nan", "-NaN"] {
+            assert!(!is_number(text), "{:?} should not be a number", text);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn numbers_parse_as_f64(text in "\\PC*") {
+            if is_number(&text) {
+                prop_assert!(f64::from_str(text.trim()).is_ok());
+            }
+        }
+
+        #[test]
+        fn numbers_follow_the_grammar(
+            space in " ?",
+            sign in "[+-]?",
+            int in "[0-9]{0,4}",
+            point in any::<bool>(),
+            fract in "[0-9]{0,3}",
+            exponent in "([eE][+-]?[0-9]{1,2})?",
+        ) {
+            let fract = if point { format!(".{}", fract) } else { String::new() };
+            let text = format!("{space}{sign}{int}{fract}{exponent}{space}");
+            // digits before or after the point, the exponent is optional
+            let expected = !int.is_empty() || fract.len() > 1;
+            prop_assert_eq!(is_number(&text), expected, "{:?}", text);
+            if is_integer(&text) {
+                prop_assert!(is_double(&text));
+            }
+        }
+
+        #[test]
+        fn formatted_integers_are_integers(n in any::<i64>(), zeros in 0usize..3, plus in any::<bool>()) {
+            let sign = if n < 0 { "-" } else if plus { "+" } else { "" };
+            let text = format!("{}{}{}", sign, "0".repeat(zeros), n.unsigned_abs());
+            prop_assert!(is_integer(&text));
+            prop_assert!(is_double(&text));
+        }
+    }
+
+    #[test]
+    fn rounded_values() {
+        assert!(!is_rounded("1.4", "1.40 "));
+        assert!(!is_rounded("326", "  326"));
+        assert!(is_rounded("3.14159", "3.14"));
+        assert!(is_rounded("12345", "12.3K"));
+        assert!(is_rounded("0.0000001234", "0.000000…"));
+        assert!(!is_rounded("NA", "NA"));
+        assert!(!is_rounded("0x1A", "26"));
+        assert!(!is_rounded("Ideal", "Ide…"));
+    }
+
+    #[test]
+    fn locale_words() {
+        let opts = FormatOptions::default();
+        assert_eq!(parse_logical("TRUE", &opts), Some(true));
+        assert_eq!(parse_logical("0", &opts), Some(false));
+        assert_eq!(parse_logical("yes", &opts), None);
+        assert_eq!(parse_logical("WAHR", &opts), None);
+        assert!(!is_missing("k.A.", &opts));
+        assert_eq!("DE".parse(), Ok(Locale::De));
+        assert!("xx".parse::<Locale>().is_err());
+
+        let opts = FormatOptions {
+            locales: vec![Locale::De, Locale::Es],
+            ..FormatOptions::default()
+        };
+        assert!(is_missing("k.A.", &opts));
+        assert!(is_missing("N/D", &opts));
+        assert!(!is_missing("n.d.", &opts));
+        assert_eq!(parse_logical("WAHR", &opts), Some(true));
+        assert_eq!(parse_logical("falso", &opts), Some(false));
+        assert_eq!(parse_logical("Verdadero", &opts), Some(true));
+        assert_eq!(parse_logical("VRAI", &opts), None);
+        assert!(!is_logical("WAHR"));
+        assert_eq!(infer_type_from_string("FALSCH", &opts), ValueType::Boolean);
+    }
+
+    #[test]
+    fn radix_integers() {
+        assert!(is_radix_integer("0x1A2B"));
+        assert!(is_radix_integer("0b1010"));
+        assert!(is_radix_integer("0o755"));
+        assert!(is_radix_integer(" -0XfF "));
+        assert!(!is_radix_integer("0x"));
+        assert!(!is_radix_integer("0b102"));
+        assert!(!is_radix_integer("x1A"));
+        assert_eq!(parse_radix_integer("0x1A2B"), Some(6699));
+        assert_eq!(parse_radix_integer("-0b1010"), Some(-10));
+        assert_eq!(parse_radix_integer("+0o17"), Some(15));
+        assert_eq!(parse_radix_integer("12"), None);
+        assert_eq!(
+            infer_type_from_string("0xFF", &FormatOptions::default()),
+            ValueType::Integer
+        );
+    }
+
+    #[test]
+    fn na_presets_and_lists() {
+        for text in ["", "NA", "NULL"] {
+            assert!(NaValues::Strict.matches(text), "{:?}", text);
+        }
+        for text in ["None", "missing", "null", "NaN", "n/a", "-"] {
+            assert!(!NaValues::Strict.matches(text), "{:?}", text);
+        }
+        assert!(NaValues::Standard.matches("None"));
+        assert!(NaValues::Standard.matches("missing"));
+        assert!(!NaValues::Standard.matches("-"));
+        assert!(NaValues::Loose.matches("-"));
+        assert!(NaValues::Loose.matches("#N/A"));
+        assert!(NaValues::Loose.matches("missing"));
+        assert!(!NaValues::Loose.matches("Nonesuch"));
+        let list = NaValues::List(vec!["".to_string(), "-999".to_string()]);
+        assert!(list.matches("-999"));
+        assert!(list.matches(""));
+        assert!(!list.matches("NA"));
+        let opts = FormatOptions {
+            na_values: list,
+            ..FormatOptions::default()
+        };
+        assert!(is_missing("-999", &opts));
+        assert!(!is_missing("NA", &opts));
+        assert_eq!(format_strings(&["x", "-999"], &opts), ["x  ", "NA "]);
+        assert!(is_na("NA"));
+        assert_eq!(infer_type_from_string("NA", &opts), ValueType::Character);
+        assert_eq!("Loose".parse(), Ok(NaValues::Loose));
+        assert!("lenient".parse::<NaValues>().is_err());
+    }
+
+    #[test]
+    fn percentages() {
+        assert!(is_percent("15.3%"));
+        assert!(is_percent(" -2 % "));
+        assert!(!is_percent("%"));
+        assert!(!is_percent("15.3"));
+        assert_eq!(to_percent("0.153"), Some("15.3%".to_string()));
+        assert_eq!(to_percent("1"), Some("100%".to_string()));
+        assert_eq!(to_percent(".5"), Some("50%".to_string()));
+        assert_eq!(to_percent("-0.005"), Some("-0.5%".to_string()));
+        assert_eq!(to_percent("12.5"), Some("1250%".to_string()));
+        assert_eq!(to_percent("1e-3"), None);
+        assert_eq!(to_percent("NA"), None);
+
+        let col = ["rate", "15.3%", "2.25%", "-100%", "NA"];
+        let opts = FormatOptions::default();
+        let shown = format_strings(&col, &opts);
+        assert_eq!(
+            shown,
+            [
+                "rate     ",
+                "  15.3%  ",
+                "   2.25% ",
+                "-100%    ",
+                "  NA     "
+            ]
+        );
+        assert_eq!(
+            cell_roles(&col, &shown, &opts),
+            [
+                CellRole::Text,
+                CellRole::Number,
+                CellRole::Number,
+                CellRole::Negative,
+                CellRole::Missing
+            ]
+        );
+    }
+
+    #[test]
+    fn dates() {
+        let iso = Some("2024-01-31".to_string());
+        assert_eq!(to_iso_date("2024-01-31"), iso);
+        assert_eq!(to_iso_date("01/31/2024"), iso);
+        assert_eq!(to_iso_date("1/31/2024"), iso);
+        assert_eq!(to_iso_date("31.01.2024"), iso);
+        assert_eq!(to_iso_date("20240131"), iso);
+        assert_eq!(to_iso_date("Jan 31, 2024"), iso);
+        assert_eq!(to_iso_date("january 31 2024"), iso);
+        assert_eq!(to_iso_date(" 31 Jan. 2024 "), iso);
+        assert_eq!(to_iso_date("Sept 1, 2024"), Some("2024-09-01".to_string()));
+        assert_eq!(to_iso_date("02/29/2024"), Some("2024-02-29".to_string()));
+        assert_eq!(to_iso_date("02/29/2023"), None);
+        assert_eq!(to_iso_date("31/01/2024"), None);
+        assert_eq!(to_iso_date("20241301"), None);
+        assert_eq!(to_iso_date("Ja 31, 2024"), None);
+        assert_eq!(to_iso_date("Mon 31, 2024"), None);
+        assert_eq!(to_iso_date("2024"), None);
+        assert!(is_date("01/31/2024"));
+        assert!(is_date("31 January 2024"));
+        assert!(!is_date("1.5"));
+        assert_eq!(
+            infer_type_from_string("31.01.2024", &FormatOptions::default()),
+            ValueType::Date
+        );
+        assert_eq!(
+            infer_type_from_string("20240131", &FormatOptions::default()),
+            ValueType::Integer
+        );
+    }
+
+    #[test]
+    fn date_times() {
+        assert!(is_date_time("2020-10-09T11:59:37Z"));
+        assert!(is_date_time("2020-10-09 11:59:37.250+02:00"));
+        assert!(is_date_time("2020-10-09 11:59 UTC"));
+        assert!(is_date_time("2020-10-09t11:59:37-0800"));
+        assert!(!is_date_time("11:59:37"));
+        assert!(!is_date_time("2020-10-09"));
+        assert!(!is_date_time("2020-13-09T11:59:37"));
+        assert!(!is_date_time("2020-10-09T11:59:37+25:00"));
+        assert_eq!(
+            infer_type_from_string("2020-10-09T11:59:37Z", &FormatOptions::default()),
+            ValueType::DateTime
+        );
+        assert_eq!(
+            infer_type_from_string("11:59:37", &FormatOptions::default()),
+            ValueType::Time
+        );
+
+        let shown = |text, tz| to_date_time(text, tz).unwrap();
+        assert_eq!(
+            shown("2020-10-09T11:59:37.987Z", None),
+            "2020-10-09 11:59:37Z"
+        );
+        assert_eq!(shown("2020-10-09T11:59", None), "2020-10-09 11:59:00");
+        assert_eq!(
+            shown("2020-10-09T11:59:37+02:00", None),
+            "2020-10-09 11:59:37+02:00"
+        );
+        let utc = "UTC".parse().ok();
+        assert_eq!(utc, Some(TimeZone::Utc));
+        assert_eq!(
+            shown("2020-10-09T01:59:37+02:00", utc),
+            "2020-10-08 23:59:37Z"
+        );
+        let india = "+05:30".parse().ok();
+        assert_eq!(
+            shown("2020-10-09T23:59:37Z", india),
+            "2020-10-10 05:29:37+05:30"
+        );
+        assert_eq!(shown("2020-10-09 23:59:37", india), "2020-10-09 23:59:37");
+        assert_eq!("Local".parse(), Ok(TimeZone::Local));
+        assert_eq!("+00".parse(), Ok(TimeZone::Utc));
+        assert!("Europe/Berlin".parse::<TimeZone>().is_err());
+        assert_eq!(to_date_time("2020-10-09", None), None);
+    }
+
+    #[test]
+    fn epoch_timestamps() {
+        assert_eq!(
+            from_epoch("1602244777", None),
+            Some("2020-10-09 11:59:37Z".to_string())
+        );
+        assert_eq!(
+            from_epoch("1602244777250", "+02:00".parse().ok()),
+            Some("2020-10-09 13:59:37+02:00".to_string())
+        );
+        assert_eq!(
+            from_epoch("0", None),
+            Some("1970-01-01 00:00:00Z".to_string())
+        );
+        assert_eq!(
+            from_epoch("-86400", None),
+            Some("1969-12-31 00:00:00Z".to_string())
+        );
+        // up to 10 digits are seconds, 13 milliseconds, 11, 12 and 14 are neither
+        assert_eq!(
+            from_epoch("999999999", None),
+            Some("2001-09-09 01:46:39Z".to_string())
+        );
+        assert_eq!(from_epoch("16022447772", None), None);
+        assert_eq!(from_epoch("160224477725", None), None);
+        assert_eq!(from_epoch("16022447772500", None), None);
+        assert_eq!(from_epoch("1.5", None), None);
+        assert_eq!(from_epoch("NA", None), None);
+
+        let opts = FormatOptions::default();
+        let column = ["1602244777", "NA", "1602244778"];
+        assert!(is_epoch_column("created_ts", &column, &opts));
+        assert!(is_epoch_column("Login_Time", &["1602244777250"], &opts));
+        assert!(!is_epoch_column("created", &column, &opts));
+        assert!(is_epoch_column("created_ts", &["999999999"], &opts));
+        assert!(!is_epoch_column("created_ts", &["16022447772"], &opts));
+        assert!(!is_epoch_column("created_ts", &["160224477725"], &opts));
+        assert!(!is_epoch_column("created_ts", &["16022447772500"], &opts));
+        assert!(!is_epoch_column("created_ts", &["1", "4.5"], &opts));
+        assert!(!is_epoch_column("created_ts", &["NA"], &opts));
+    }
+
+    #[test]
+    fn decimal_comma() {
+        assert_eq!(to_decimal_point("3,14"), Some("3.14".to_string()));
+        assert_eq!(to_decimal_point(" -0,5 "), Some("-0.5".to_string()));
+        assert_eq!(to_decimal_point(",5"), Some(".5".to_string()));
+        assert_eq!(to_decimal_point("1.234,5"), Some("1234.5".to_string()));
+        assert_eq!(to_decimal_point("1.234.567"), Some("1234567".to_string()));
+        assert_eq!(to_decimal_point("12"), None);
+        assert_eq!(to_decimal_point("3.14"), None);
+        assert_eq!(to_decimal_point("1,2,3"), None);
+        assert_eq!(to_decimal_point("12.34,5"), None);
+
+        let opts = FormatOptions {
+            decimal_comma: true,
+            ..FormatOptions::default()
+        };
+        let col = ["x", "3.14159", "-12.5", "NA", "7", "a.b"];
+        assert_eq!(
+            format_strings(&col, &opts),
+            ["x      ", "  3,14 ", "-12,5  ", " NA    ", "  7    ", "a.b    "]
+        );
+        let shown = format_strings(&col, &opts);
+        assert_eq!(
+            cell_roles(&col, &shown, &opts)[1..3],
+            [CellRole::Number, CellRole::Negative]
+        );
+        assert!(is_rounded("3.14159", "3,14"));
+        assert!(!is_rounded("-12.5", "-12,5"));
+    }
+
+    #[test]
+    fn radix_columns_are_right_aligned_or_converted() {
+        let col = ["flags", "0x1", "0xFF", "0x1A2B"];
+        let opts = FormatOptions::default();
+        assert_eq!(
+            format_strings(&col, &opts),
+            ["flags  ", "   0x1 ", "  0xFF ", "0x1A2B "]
+        );
+        let opts = FormatOptions {
+            radix: Radix::Dec,
+            ..opts
+        };
+        assert_eq!(
+            format_strings(&col, &opts),
+            ["flags ", "1     ", "255   ", "6699  "]
+        );
+    }
+
+    #[test]
+    fn format_column_uses_the_given_type() {
+        let opts = FormatOptions::default();
+        let values = ["code", "1.50", "007", "NA", "abc"];
+        assert_eq!(
+            format_column(&values, ValueType::Character, &opts),
+            ["code ", "1.50 ", "007  ", "NA   ", "abc  "]
+        );
+        assert_eq!(
+            format_column(&values, ValueType::Double, &opts),
+            ["code ", "1.5  ", "7    ", "NA   ", "NA   "]
+        );
+        assert_eq!(
+            format_column(&["2021", "1", "2"], ValueType::Integer, &opts),
+            ["2021 ", "1    ", "2    "]
+        );
+        assert_eq!(
+            format_column(&["x", "a", ""], ValueType::Na, &opts),
+            ["x  ", "NA ", "NA "]
+        );
+    }
+
+    #[test]
+    fn literal_na_strings() {
+        let values = ["name", "NA", "null", "", "Bob"];
+        assert!(is_missing("NA", &FormatOptions::default()));
+        assert_eq!(
+            format_strings(&values, &FormatOptions::default()),
+            ["name ", "NA   ", "NA   ", "NA   ", "Bob  "]
+        );
+        let opts = FormatOptions {
+            na_strings: false,
+            ..FormatOptions::default()
+        };
+        assert!(!is_missing("NA", &opts));
+        assert!(is_missing("", &opts));
+        assert_eq!(infer_type_from_string("NA", &opts), ValueType::Character);
+        assert_eq!(infer_type_from_string("", &opts), ValueType::Na);
+        assert_eq!(
+            format_strings(&values, &opts),
+            // an empty field is blank, so it differs from a literal NA
+            ["name ", "NA   ", "null ", "     ", "Bob  "]
+        );
+        // a cell of a numeric column that is not a number is missing, blank too
+        assert_eq!(
+            format_column(&["x", "1.5", "abc", ""], ValueType::Double, &opts),
+            ["x   ", "1.5 ", "    ", "    "]
+        );
+    }
 
     #[test]
     fn one_byte_delimiter() {
-        assert_eq!(parse_delimiter(","), Ok(b','));
-        assert_eq!(parse_delimiter(";"), Ok(b';'));
-        assert_eq!(parse_delimiter("|"), Ok(b'|'));
-        assert_eq!(parse_delimiter(" "), Ok(b' '));
-        assert_eq!(parse_delimiter("\t"), Ok(b'\t'));
+        assert_eq!(parse_delimiter(",").as_deref(), Ok(&b","[..]));
+        assert_eq!(parse_delimiter(";").as_deref(), Ok(&b";"[..]));
+        assert_eq!(parse_delimiter("|").as_deref(), Ok(&b"|"[..]));
+        assert_eq!(parse_delimiter(" ").as_deref(), Ok(&b" "[..]));
+        assert_eq!(parse_delimiter("\t").as_deref(), Ok(&b"\t"[..]));
     }
 
     #[test]
     fn tab_delimiter() {
-        assert_eq!(parse_delimiter("\\t"), Ok(b'\t'));
+        assert_eq!(parse_delimiter("\\t").as_deref(), Ok(&b"\t"[..]));
+    }
+
+    #[test]
+    fn named_delimiter() {
+        assert_eq!(parse_delimiter("comma").as_deref(), Ok(&b","[..]));
+        assert_eq!(parse_delimiter("tab").as_deref(), Ok(&b"\t"[..]));
+        assert_eq!(parse_delimiter("pipe").as_deref(), Ok(&b"|"[..]));
+        assert_eq!(parse_delimiter("semicolon").as_deref(), Ok(&b";"[..]));
+        assert_eq!(parse_delimiter("space").as_deref(), Ok(&b" "[..]));
+        assert_eq!(parse_delimiter("tabs").as_deref(), Ok(&b"tabs"[..]));
+    }
+
+    #[test]
+    fn escaped_and_multi_byte_delimiter() {
+        assert_eq!(parse_delimiter("\\x01").as_deref(), Ok(&b"\x01"[..]));
+        assert_eq!(parse_delimiter("\\x1F").as_deref(), Ok(&b"\x1f"[..]));
+        assert_eq!(parse_delimiter("\\\\").as_deref(), Ok(&b"\\"[..]));
+        assert_eq!(parse_delimiter("||").as_deref(), Ok(&b"||"[..]));
+        assert_eq!(parse_delimiter("\\t|").as_deref(), Ok(&b"\t|"[..]));
+        assert_eq!(parse_delimiter("too long").as_deref(), Ok(&b"too long"[..]));
+    }
+
+    #[test]
+    fn sigfig_range() {
+        assert_eq!(parse_sigfig("1"), Ok(1));
+        assert_eq!(parse_sigfig("15"), Ok(15));
+        assert!(parse_sigfig("0").is_err());
+        assert!(parse_sigfig("16").is_err());
+        assert!(parse_sigfig("-3").is_err());
+        assert!(parse_sigfig("three").is_err());
+    }
+
+    #[test]
+    fn percent() {
+        assert_eq!(parse_percent("50"), Ok(50.0));
+        assert_eq!(parse_percent("12.5%"), Ok(12.5));
+        assert_eq!(parse_percent("0"), Ok(0.0));
+        assert!(parse_percent("101").is_err());
+        assert!(parse_percent("-1").is_err());
+        assert!(parse_percent("half").is_err());
+    }
+
+    #[test]
+    fn comment_character() {
+        assert_eq!(parse_comment("#"), Ok(b'#'));
+        assert_eq!(parse_comment("%"), Ok(b'%'));
+        assert!(parse_comment("").is_err());
+        assert!(parse_comment("//").is_err());
     }
 
     #[test]
     fn delimiter_wrong_length() {
         assert_eq!(
             parse_delimiter(""),
-            Err("expected one byte as delimiter, got 0 bytes (\"\")".to_string())
+            Err("expected a delimiter, got an empty string".to_string())
         );
         assert_eq!(
-            parse_delimiter("too long"),
-            Err("expected one byte as delimiter, got 8 bytes (\"too long\")".to_string())
+            parse_delimiter("\\n"),
+            Err("unknown escape in delimiter \"\\n\", expected \\t, \\\\ or \\xNN".to_string())
         );
         assert_eq!(
-            parse_delimiter("\\n"),
-            Err("expected one byte as delimiter, got 2 bytes (\"\\n\")".to_string())
+            parse_delimiter("\\x1"),
+            Err("unknown escape in delimiter \"\\x1\", expected \\t, \\\\ or \\xNN".to_string())
         );
+        assert_eq!(
+            parse_delimiter("\\x0a"),
+            Err("a newline can not be a delimiter (\"\\x0a\")".to_string())
+        );
+    }
+
+    // Column alignment must follow display width, not bytes or chars, on every
+    // platform (the Windows console included): CJK and most emoji take two cells.
+    #[test]
+    fn wide_characters_are_padded_by_display_width() {
+        use unicode_width::UnicodeWidthStr;
+
+        let values = ["name", "東京", "🦀 crab", "naïve", "abc"];
+        let formatted = format_strings(&values, &FormatOptions::default());
+        let widths: Vec<usize> = formatted.iter().map(|s| s.width()).collect();
+        assert!(widths.iter().all(|&w| w == widths[0]), "{:?}", formatted);
+        assert_eq!(widths[0], "🦀 crab".width() + 1);
+
+        let narrow = FormatOptions {
+            upper_column_width: 5,
+            ..Default::default()
+        };
+        let truncated = format_strings(&["name", "東京都庁舎"], &narrow);
+        assert!(truncated.iter().all(|s| s.width() == 6), "{:?}", truncated);
+    }
+
+    #[test]
+    fn cells_have_roles() {
+        let raw = ["delta", "-1.5", "2", "NA", "n/a", "-12 km"];
+        let opts = FormatOptions::default();
+        let shown = format_strings(&raw, &opts);
+        assert_eq!(
+            cell_roles(&raw, &shown, &opts),
+            [
+                CellRole::Text,
+                CellRole::Negative,
+                CellRole::Number,
+                CellRole::Missing,
+                CellRole::Missing,
+                CellRole::Text,
+            ]
+        );
+        let literal = FormatOptions {
+            na_strings: false,
+            ..Default::default()
+        };
+        let shown = format_strings(&raw, &literal);
+        assert_eq!(cell_roles(&raw, &shown, &literal)[3], CellRole::Text);
+    }
+
+    #[test]
+    fn headers_are_annotated() {
+        use unicode_width::UnicodeWidthStr;
+
+        let opts = FormatOptions {
+            upper_column_width: 8,
+            ..Default::default()
+        };
+        let annotated = |values: &[&str]| {
+            let mut column = format_strings(values, &opts);
+            annotate_header(&mut column, values[0], "\u{25b2}", &opts);
+            column
+        };
+        // widened for the annotation
+        assert_eq!(
+            annotated(&["x", "10", "2"]),
+            ["x \u{25b2} ", "10  ", "2   "]
+        );
+        // fits beside the name
+        assert_eq!(annotated(&["x", "123456"])[0], "x \u{25b2}    ");
+        // the name is cut short at the upper column width
+        let column = annotated(&["carat_weight", "0.23"]);
+        assert_eq!(column[0], "carat\u{2026} \u{25b2} ");
+        assert!(column.iter().all(|cell| cell.width() == 9), "{:?}", column);
+    }
+
+    #[test]
+    fn columns_are_no_narrower_than_their_min_width() {
+        use unicode_width::UnicodeWidthStr;
+
+        let opts = FormatOptions::default();
+        let columns: [&[&str]; 5] = [
+            &["x", "1.5", "10"],
+            &["name", "NA", "Ann"],
+            &["a_very_long_column_name", "1"],
+            &["\u{65e5}\u{672c}", "1234567.891"],
+            &["price", "a long value in a short column"],
+        ];
+        for column in columns {
+            let min_width = min_column_width(column[0], &opts);
+            let formatted = format_strings(column, &opts);
+            assert!(formatted[0].width() >= min_width, "{:?}", formatted);
+        }
+        assert_eq!(min_column_width("x", &opts), 3);
+        assert_eq!(min_column_width("a_very_long_column_name", &opts), 21);
+    }
+
+    #[test]
+    fn truncation_keeps_grapheme_clusters_whole() {
+        use unicode_width::UnicodeWidthStr;
+
+        let family = "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}";
+        let flag = "\u{1f1ef}\u{1f1f5}";
+        let text = format!("{}{}{}", family, flag, family);
+        assert_eq!(
+            truncate_graphemes(&text, 5),
+            (&text[..family.len() + flag.len()], 4)
+        );
+        assert_eq!(truncate_graphemes(&text, 1), ("", 0));
+        assert_eq!(truncate_graphemes("e\u{301}te\u{301}", 2), ("e\u{301}t", 2));
+
+        let narrow = FormatOptions {
+            upper_column_width: 5,
+            ..Default::default()
+        };
+        let values = [
+            "emoji",
+            &text,
+            "\u{1f1eb}\u{1f1f7}\u{1f1e9}\u{1f1ea}\u{1f1ee}\u{1f1f9}",
+        ];
+        let truncated = format_strings(&values, &narrow);
+        assert_eq!(truncated[1], format!("{}{}\u{2026} ", family, flag));
+        assert!(truncated.iter().all(|s| s.width() == 6), "{:?}", truncated);
     }
 }
@@ -0,0 +1,298 @@
+// A tiny arithmetic expression engine.
+//
+// Expressions are made of numbers, column names, parentheses and the binary
+// operators `+ - * / ^`. Column names that are not plain identifiers (spaces,
+// dashes, ...) can be quoted with backticks: `` `unit price` * qty ``.
+//
+// A cell that is NA or not a number makes the whole expression NA, the same
+// way arithmetic with NA works in R.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Column(String),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+/// A derived column: `name = expression`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mutation {
+    pub name: String,
+    pub expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+}
+
+impl Expr {
+    /// Evaluate the expression. `lookup` returns the numeric value of a column
+    /// for the current row, or `None` if it is missing.
+    pub fn eval(&self, lookup: &dyn Fn(&str) -> Option<f64>) -> Option<f64> {
+        let value = match self {
+            Expr::Number(x) => *x,
+            Expr::Column(name) => lookup(name)?,
+            Expr::Neg(e) => -e.eval(lookup)?,
+            Expr::Binary(lhs, op, rhs) => {
+                let (a, b) = (lhs.eval(lookup)?, rhs.eval(lookup)?);
+                match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                    Op::Pow => a.powf(b),
+                }
+            }
+        };
+        // division by zero and friends are treated as missing
+        if value.is_finite() {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// All column names referenced by the expression.
+    pub fn columns(&self) -> Vec<&str> {
+        match self {
+            Expr::Number(_) => vec![],
+            Expr::Column(name) => vec![name.as_str()],
+            Expr::Neg(e) => e.columns(),
+            Expr::Binary(lhs, _, rhs) => {
+                let mut v = lhs.columns();
+                v.extend(rhs.columns());
+                v
+            }
+        }
+    }
+}
+
+pub fn parse_mutation(src: &str) -> Result<Mutation, String> {
+    let (name, expr) = src
+        .split_once('=')
+        .ok_or_else(|| format!("expected \"name = expression\", got \"{}\"", src))?;
+    let name = name.trim().trim_matches('`').to_string();
+    if name.is_empty() {
+        return Err(format!("missing column name in \"{}\"", src));
+    }
+    Ok(Mutation {
+        name,
+        expr: parse(expr)?,
+    })
+}
+
+pub fn parse(src: &str) -> Result<Expr, String> {
+    let tokens = tokenize(src)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.expression()?;
+    match parser.tokens.get(parser.pos) {
+        None => Ok(expr),
+        Some(t) => Err(format!("unexpected {:?} in \"{}\"", t, src.trim())),
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = src.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '+' | '-' | '*' | '/' | '^' => {
+                tokens.push(Token::Op(c));
+                chars.next();
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                chars.next();
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                chars.next();
+            }
+            '`' => {
+                chars.next();
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('`') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("unterminated ` in \"{}\"", src.trim())),
+                    }
+                }
+                tokens.push(Token::Ident(name));
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek() {
+                    let is_exponent_sign = (c == '-' || c == '+')
+                        && (number.ends_with('e') || number.ends_with('E'));
+                    if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || is_exponent_sign
+                    {
+                        number.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let value = number
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid number \"{}\"", number))?;
+                tokens.push(Token::Number(value));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '.' {
+                        name.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Ident(name));
+            }
+            _ => return Err(format!("unexpected character '{}' in \"{}\"", c, src.trim())),
+        }
+    }
+    Ok(tokens)
+}
+
+// recursive descent, lowest precedence first:
+//   expression = term (('+' | '-') term)*
+//   term       = unary (('*' | '/') unary)*
+//   unary      = '-' unary | power
+//   power      = atom ('^' unary)?
+//   atom       = number | column | '(' expression ')'
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next_op(&self, ops: &[char]) -> Option<char> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(c)) if ops.contains(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        while let Some(c) = self.next_op(&['+', '-']) {
+            self.pos += 1;
+            let op = if c == '+' { Op::Add } else { Op::Sub };
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.term()?));
+        }
+        Ok(lhs)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        while let Some(c) = self.next_op(&['*', '/']) {
+            self.pos += 1;
+            let op = if c == '*' { Op::Mul } else { Op::Div };
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.next_op(&['-']).is_some() {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if self.next_op(&['^']).is_some() {
+            self.pos += 1;
+            return Ok(Expr::Binary(
+                Box::new(base),
+                Op::Pow,
+                Box::new(self.unary()?),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Number(x)) => Ok(Expr::Number(x)),
+            Some(Token::Ident(name)) => Ok(Expr::Column(name)),
+            Some(Token::LParen) => {
+                let expr = self.expression()?;
+                match self.tokens.get(self.pos) {
+                    Some(Token::RParen) => {
+                        self.pos += 1;
+                        Ok(expr)
+                    }
+                    _ => Err("expected ')'".to_string()),
+                }
+            }
+            Some(t) => Err(format!("unexpected {:?}", t)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_with(expr: &str, vars: &[(&str, f64)]) -> Option<f64> {
+        let lookup = |name: &str| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| *v);
+        parse(expr).unwrap().eval(&lookup)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval_with("1 + 2 * 3", &[]), Some(7.0));
+        assert_eq!(eval_with("(1 + 2) * 3", &[]), Some(9.0));
+        assert_eq!(eval_with("2 ^ 3 ^ 2", &[]), Some(512.0));
+        assert_eq!(eval_with("-2 ^ 2", &[]), Some(-4.0));
+        assert_eq!(eval_with("10 - 4 - 3", &[]), Some(3.0));
+        assert_eq!(eval_with("1.5e2 / 3", &[]), Some(50.0));
+    }
+
+    #[test]
+    fn columns_and_missing_values() {
+        let vars = [("revenue", 200.0), ("cost", 150.0), ("unit price", 2.0)];
+        assert_eq!(eval_with("(revenue - cost)/revenue", &vars), Some(0.25));
+        assert_eq!(eval_with("`unit price` * 3", &vars), Some(6.0));
+        assert_eq!(eval_with("revenue + tax", &vars), None);
+        assert_eq!(eval_with("revenue / 0", &vars), None);
+    }
+
+    #[test]
+    fn mutation_parsing() {
+        let m = parse_mutation("margin = (revenue - cost)/revenue").unwrap();
+        assert_eq!(m.name, "margin");
+        assert_eq!(m.expr.columns(), vec!["revenue", "cost", "revenue"]);
+        assert!(parse_mutation("revenue - cost").is_err());
+        assert!(parse_mutation(" = 1").is_err());
+        assert!(parse_mutation("x = (1 + 2").is_err());
+        assert!(parse_mutation("x = 1 +").is_err());
+        assert!(parse_mutation("x = 1 $ 2").is_err());
+    }
+}
@@ -0,0 +1,291 @@
+// Detecting the input format from its content, for input whose extension
+// does not say (stdin, `data`, `export.txt`, a `.csv` that uses semicolons).
+use encoding_rs::Encoding;
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use std::io::{self, Read};
+
+/// What the first bytes of the input look like. tv reads delimited text and
+/// the first table of an HTML page; the other formats are reported instead of
+/// being parsed as csv.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Content {
+    Parquet,
+    Arrow,
+    /// The legacy Feather V1 layout of early pandas/pyarrow, which predates Arrow IPC.
+    FeatherV1,
+    Json,
+    Html,
+    /// Some other binary format, anything with NUL bytes in the first bytes.
+    Binary,
+    Text,
+}
+
+impl Content {
+    /// Detect the content from the first bytes of the input.
+    pub fn sniff(head: &[u8]) -> Content {
+        if head.starts_with(b"PAR1") {
+            return Content::Parquet;
+        }
+        if head.starts_with(b"ARROW1") {
+            return Content::Arrow;
+        }
+        if head.starts_with(b"FEA1") {
+            return Content::FeatherV1;
+        }
+        // UTF-16 text has NUL bytes too, it is decoded by its byte order mark
+        if head.starts_with(b"\xff\xfe") || head.starts_with(b"\xfe\xff") {
+            return Content::Text;
+        }
+        if head[..head.len().min(512)].contains(&0) {
+            return Content::Binary;
+        }
+        let text = String::from_utf8_lossy(&head[..head.len().min(512)]);
+        let text = text.trim_start_matches('\u{feff}').trim_start();
+        let lower = text.to_ascii_lowercase();
+        if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+            return Content::Html;
+        }
+        if lower.starts_with("<table") {
+            return Content::Html;
+        }
+        let mut bytes = head.iter().skip_while(|b| b.is_ascii_whitespace());
+        match bytes.next() {
+            Some(b'{') => Content::Json,
+            // `[{...}]`, `[[...]]` or `["..."]`, not a csv header like `[id],name`
+            Some(b'[') => match bytes.find(|b| !b.is_ascii_whitespace()) {
+                Some(b'{' | b'[' | b'"') => Content::Json,
+                _ => Content::Text,
+            },
+            _ => Content::Text,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Content::Parquet => "Parquet",
+            Content::Arrow => "Arrow IPC",
+            Content::FeatherV1 => "Feather V1",
+            Content::Json => "JSON",
+            Content::Html => "HTML",
+            Content::Binary => "binary",
+            Content::Text => "text",
+        }
+    }
+
+    /// The message shown for content tv can not display, `None` for text and HTML.
+    pub fn unsupported(self) -> Option<String> {
+        match self {
+            Content::Text | Content::Html => None,
+            Content::FeatherV1 => Some(
+                "Feather V1 data is not supported. Convert it to csv, for example in Python with pandas.read_feather(\"data.feather\").to_csv(\"data.csv\", index=False).".to_string(),
+            ),
+            Content::Binary => Some(
+                "binary data in an unknown format. tv reads delimited text (csv, tsv, psv) and HTML tables; compressed files need their extension (.gz, .zst, .bz2, .xz).".to_string(),
+            ),
+            _ => Some(format!(
+                "{} data is not supported. tv reads delimited text (csv, tsv, psv) and HTML tables.",
+                self.name()
+            )),
+        }
+    }
+}
+
+/// Parse `--encoding`: any WHATWG label like latin1, windows-1252, utf-16le
+/// or shift_jis. `utf16` is accepted for `utf-16`.
+pub fn parse_encoding(label: &str) -> Result<&'static Encoding, String> {
+    let label = label.to_ascii_lowercase();
+    Encoding::for_label(label.as_bytes())
+        .or_else(|| {
+            let rest = label.strip_prefix("utf")?;
+            Encoding::for_label(format!("utf-{}", rest).as_bytes())
+        })
+        .ok_or_else(|| format!("unknown encoding \"{}\"", label))
+}
+
+/// Transcode `source` to UTF-8 from `encoding`, or from the encoding of its
+/// byte order mark. Without either the bytes are passed through as they are.
+pub fn decode(source: Box<dyn Read>, encoding: Option<&'static Encoding>) -> Box<dyn Read> {
+    Box::new(
+        DecodeReaderBytesBuilder::new()
+            .encoding(encoding)
+            .strip_bom(true)
+            .build(source),
+    )
+}
+
+/// How many bytes at the start of the input are sniffed.
+const SNIFF_BYTES: u64 = 8 * 1024;
+
+/// The first bytes of `source` to sniff, and a reader that still returns every
+/// byte. Unlike `BufReader::fill_buf` this keeps reading until the sample is
+/// full, decoders often return only a few bytes at first.
+pub fn peek(mut source: Box<dyn Read>) -> io::Result<(Vec<u8>, Box<dyn Read>)> {
+    let mut head = Vec::new();
+    (&mut source).take(SNIFF_BYTES).read_to_end(&mut head)?;
+    Ok((head.clone(), Box::new(io::Cursor::new(head).chain(source))))
+}
+
+/// Delimiters tried by [`sniff_delimiter`], in order of preference on a tie.
+const DELIMITERS: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+/// How many lines of the input are sampled to guess the delimiter.
+const SNIFF_LINES: usize = 20;
+
+/// Guess the delimiter from the first lines of the input, like Python's
+/// csv.Sniffer. A delimiter that splits every sampled line into the same
+/// number of fields wins, the one giving the most fields first; otherwise the
+/// most frequent one in the header. Quoted fields and lines starting with the
+/// `comment` byte are skipped. `None` if no candidate appears at all.
+pub fn sniff_delimiter(head: &[u8], comment: Option<u8>) -> Option<u8> {
+    let mut lines: Vec<&[u8]> = head
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.iter().all(|b| b.is_ascii_whitespace()))
+        .filter(|line| comment.is_none() || line.first() != comment.as_ref())
+        .collect();
+    // the last line may be cut off by the end of the sample
+    if lines.len() > 1 && !head.ends_with(b"\n") {
+        lines.pop();
+    }
+    lines.truncate(SNIFF_LINES);
+    let header = lines.first()?;
+
+    let consistent = DELIMITERS
+        .iter()
+        .map(|&d| {
+            let n = count_unquoted(header, d);
+            let same = lines.iter().all(|line| count_unquoted(line, d) == n);
+            (d, if same { n } else { 0 })
+        })
+        .filter(|&(_, n)| n > 0);
+    pick_most(consistent).or_else(|| {
+        pick_most(
+            DELIMITERS
+                .iter()
+                .map(|&d| (d, count_unquoted(header, d)))
+                .filter(|&(_, n)| n > 0),
+        )
+    })
+}
+
+/// `head` without its first `n` rows, for sniffing below a preamble. Blank
+/// lines are not rows, as for the csv reader.
+pub fn skip_lines(head: &[u8], n: usize) -> &[u8] {
+    let mut rest = head;
+    let mut skipped = 0;
+    while skipped < n {
+        let (line, tail) = match rest.iter().position(|&b| b == b'\n') {
+            Some(i) => (&rest[..i], &rest[i + 1..]),
+            None => return &[],
+        };
+        if !line.iter().all(|b| b.is_ascii_whitespace()) {
+            skipped += 1;
+        }
+        rest = tail;
+    }
+    rest
+}
+
+// the candidate with the highest count; on a tie the earlier one
+fn pick_most(candidates: impl Iterator<Item = (u8, usize)>) -> Option<u8> {
+    candidates
+        .fold(None, |best: Option<(u8, usize)>, (d, n)| match best {
+            Some((_, m)) if m >= n => best,
+            _ => Some((d, n)),
+        })
+        .map(|(d, _)| d)
+}
+
+fn count_unquoted(line: &[u8], delimiter: u8) -> usize {
+    let mut quoted = false;
+    let mut n = 0;
+    for &b in line {
+        if b == b'"' {
+            quoted = !quoted;
+        } else if b == delimiter && !quoted {
+            n += 1;
+        }
+    }
+    n
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_content() {
+        assert_eq!(Content::sniff(b"PAR1\x15\x04"), Content::Parquet);
+        assert_eq!(Content::sniff(b"ARROW1\0\0"), Content::Arrow);
+        assert_eq!(Content::sniff(b"FEA1\0\0\0\0"), Content::FeatherV1);
+        assert_eq!(Content::sniff(b"  {\"a\": 1}\n{\"a\": 2}"), Content::Json);
+        assert_eq!(Content::sniff(b"[\n  {\"a\": 1}\n]"), Content::Json);
+        assert_eq!(Content::sniff(b"[id],name\n1,a\n"), Content::Text);
+        assert_eq!(Content::sniff(b"a,b\n1,2\n"), Content::Text);
+        assert_eq!(Content::sniff(b"\n<!DOCTYPE html>\n<html>"), Content::Html);
+        assert_eq!(Content::sniff(b"<table><tr>"), Content::Html);
+        assert_eq!(Content::sniff(b"<id>,name\n"), Content::Text);
+        assert_eq!(Content::sniff(b"\x1f\x8b\x08\0\0"), Content::Binary);
+        assert_eq!(Content::sniff(b"\xff\xfea\0,\0b\0"), Content::Text);
+        assert_eq!(Content::sniff(b"PA"), Content::Text);
+        assert_eq!(Content::sniff(b""), Content::Text);
+        assert!(Content::Text.unsupported().is_none());
+        assert!(Content::Parquet.unsupported().is_some());
+    }
+
+    #[test]
+    fn decodes_other_encodings() {
+        let read = |bytes: &'static [u8], encoding| {
+            let mut text = String::new();
+            decode(Box::new(bytes), encoding)
+                .read_to_string(&mut text)
+                .unwrap();
+            text
+        };
+        let latin1 = parse_encoding("latin1").ok();
+        assert_eq!(read(b"caf\xe9;cr\xe8me\n", latin1), "café;crème\n");
+        // utf-16 is recognized by its byte order mark
+        assert_eq!(read(b"\xff\xfea\0,\0b\0", None), "a,b");
+        assert_eq!(read(b"\xef\xbb\xbfa,b", None), "a,b");
+        assert_eq!(parse_encoding("UTF16"), Ok(encoding_rs::UTF_16LE));
+        assert!(parse_encoding("klingon").is_err());
+    }
+
+    #[test]
+    fn peeks_without_consuming() {
+        let (head, mut source) = peek(decode(Box::new(&b"a;b\n1;2\n"[..]), None)).unwrap();
+        assert_eq!(head, b"a;b\n1;2\n");
+        let mut all = Vec::new();
+        source.read_to_end(&mut all).unwrap();
+        assert_eq!(all, head);
+    }
+
+    #[test]
+    fn sniffs_delimiter() {
+        assert_eq!(sniff_delimiter(b"a,b,c\n1,2,3\n", None), Some(b','));
+        assert_eq!(sniff_delimiter(b"a\tb\tc\n1\t2\t3\n", None), Some(b'\t'));
+        assert_eq!(sniff_delimiter(b"a|b, c|d\n", None), Some(b'|'));
+        assert_eq!(sniff_delimiter(b"single column\n1\n", None), None);
+        // European csv: decimal commas are not consistent, semicolons are
+        assert_eq!(
+            sniff_delimiter(b"name;price;qty\napple;1,5;3\npear;2;10,25\n", None),
+            Some(b';')
+        );
+        // delimiters inside quotes do not count
+        assert_eq!(
+            sniff_delimiter(b"name,note\n\"Smith; John\",\"a; b; c\"\n", None),
+            Some(b',')
+        );
+        // a line cut off at the end of the sample is ignored
+        assert_eq!(sniff_delimiter(b"a;b\n1;2\n3;", None), Some(b';'));
+        // a banner above the header is skipped
+        let head = b"Sales report\n\nregion\tq1\tq2\nnorth\t1\t2\n";
+        assert_eq!(sniff_delimiter(head, None), None);
+        assert_eq!(sniff_delimiter(skip_lines(head, 1), None), Some(b'\t'));
+        assert_eq!(skip_lines(head, 9), b"");
+        // metadata lines before the data do not count
+        assert_eq!(
+            sniff_delimiter(b"# exported 2021, v2, ok\na;b\n1;2\n", Some(b'#')),
+            Some(b';')
+        );
+    }
+}
@@ -0,0 +1,116 @@
+// A vertical view of the data, one line per column, like dplyr::glimpse().
+use crate::output;
+use tidy_viewer::datatype::{self, FormatOptions, ValueType};
+use unicode_width::UnicodeWidthStr;
+
+/// The line of one column.
+pub struct Line {
+    /// The column name, padded to the widest name.
+    pub name: String,
+    /// The type, like `<dbl>`, padded to the widest type.
+    pub dtype: String,
+    /// The first values, cut to fit the width with `…`.
+    pub values: String,
+}
+
+/// One line per column of `columns`, the header first in each column, that
+/// fits `width`. Character values are quoted and missing values shown as NA.
+pub fn glimpse(columns: &[Vec<&str>], width: usize, opts: &FormatOptions) -> Vec<Line> {
+    let name_width = columns
+        .iter()
+        .map(|col| col.first().map_or(0, |name| name.width()))
+        .max()
+        .unwrap_or(0);
+    let types: Vec<String> = columns
+        .iter()
+        .map(|col| {
+            format!(
+                "<{}>",
+                abbreviation(output::column_type(&col[1.min(col.len())..], opts))
+            )
+        })
+        .collect();
+    let type_width = types.iter().map(|t| t.len()).max().unwrap_or(0);
+    // `$ name <type> values`
+    let room = width
+        .saturating_sub(2 + name_width + 1 + type_width + 1)
+        .max(1);
+
+    columns
+        .iter()
+        .zip(types)
+        .map(|(col, dtype)| {
+            let name = col.first().copied().unwrap_or_default();
+            let is_character = dtype == "<chr>";
+            let mut values = String::new();
+            for (i, &value) in col.iter().enumerate().skip(1) {
+                if i > 1 {
+                    values.push_str(", ");
+                }
+                if datatype::is_missing(value, opts) {
+                    values.push_str("NA");
+                } else if is_character {
+                    values.push_str(&format!("\"{}\"", value));
+                } else {
+                    values.push_str(value.trim());
+                }
+                if values.width() > room {
+                    break;
+                }
+            }
+            if values.width() > room {
+                let (cut, _) = datatype::truncate_graphemes(&values, room - 1);
+                values = format!("{}…", cut);
+            }
+            Line {
+                name: format!("{}{}", name, " ".repeat(name_width - name.width())),
+                dtype: format!("{:<w$}", dtype, w = type_width),
+                values,
+            }
+        })
+        .collect()
+}
+
+fn abbreviation(dtype: ValueType) -> &'static str {
+    match dtype {
+        ValueType::Boolean => "lgl",
+        ValueType::Integer => "int",
+        ValueType::Double => "dbl",
+        ValueType::Date => "date",
+        ValueType::Time => "time",
+        ValueType::DateTime => "dttm",
+        ValueType::Character => "chr",
+        // a column without values is logical in R
+        ValueType::Na => "lgl",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glimpses_each_column() {
+        let columns = vec![
+            vec!["carat", "0.23", "0.21", "0.23"],
+            vec!["cut", "Ideal", "", "Good"],
+            vec!["date", "2021-01-01", "2021-01-02", "NA"],
+            vec!["empty", "", "", ""],
+        ];
+        let lines = glimpse(&columns, 40, &FormatOptions::default());
+        let text: Vec<String> = lines
+            .iter()
+            .map(|line| format!("$ {} {} {}", line.name, line.dtype, line.values))
+            .collect();
+        assert_eq!(
+            text,
+            vec![
+                "$ carat <dbl>  0.23, 0.21, 0.23",
+                "$ cut   <chr>  \"Ideal\", NA, \"Good\"",
+                "$ date  <date> 2021-01-01, 2021-01-02, …",
+                "$ empty <lgl>  NA, NA, NA",
+            ]
+        );
+        assert!(text.iter().all(|line| line.width() <= 40));
+    }
+}
@@ -0,0 +1,231 @@
+// Reading the first <table> of an HTML page.
+//
+// This is not a full HTML parser. It walks the tags of the first table and
+// collects the text of its cells, which is enough for scraped pages and
+// exported reports. Tables nested inside a cell are flattened into that cell.
+
+/// The rows of the first `<table>` in `html`, `None` if there is none.
+pub fn first_table(html: &str) -> Option<Vec<Vec<String>>> {
+    let lower = html.to_ascii_lowercase();
+    let start = find_tag(&lower, 0, "table")?;
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut cell: Option<String> = None;
+    let mut colspan = 1;
+    let mut depth = 0;
+    let mut pos = start;
+    while pos < html.len() {
+        let next_tag = match html[pos..].find('<') {
+            Some(i) => pos + i,
+            None => html.len(),
+        };
+        if let Some(text) = cell.as_mut() {
+            text.push_str(&html[pos..next_tag]);
+        }
+        if next_tag >= html.len() {
+            break;
+        }
+        // comments can contain anything, including tags
+        if lower[next_tag..].starts_with("<!--") {
+            pos = match lower[next_tag..].find("-->") {
+                Some(i) => next_tag + i + 3,
+                None => html.len(),
+            };
+            continue;
+        }
+        let tag_end = match html[next_tag..].find('>') {
+            Some(i) => next_tag + i + 1,
+            None => html.len(),
+        };
+        let tag = &lower[next_tag + 1..tag_end.saturating_sub(1).max(next_tag + 1)];
+        let closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        pos = tag_end;
+
+        match (name.as_str(), closing) {
+            ("table", false) => depth += 1,
+            ("table", true) => {
+                depth -= 1;
+                if depth == 0 {
+                    end_cell(&mut rows, &mut cell, colspan);
+                    break;
+                }
+            }
+            // tags of a nested table only separate words of the outer cell
+            (_, _) if depth > 1 => push_space(&mut cell),
+            ("tr", false) => {
+                end_cell(&mut rows, &mut cell, colspan);
+                rows.push(Vec::new());
+            }
+            ("tr", true) => end_cell(&mut rows, &mut cell, colspan),
+            ("td" | "th", false) => {
+                end_cell(&mut rows, &mut cell, colspan);
+                if rows.is_empty() {
+                    rows.push(Vec::new());
+                }
+                cell = Some(String::new());
+                colspan = attribute(tag, "colspan")
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .unwrap_or(1)
+                    .max(1);
+            }
+            ("td" | "th", true) => end_cell(&mut rows, &mut cell, colspan),
+            // <br>, <p>, <div> ... inside a cell separate words
+            _ => push_space(&mut cell),
+        }
+    }
+
+    rows.retain(|row| !row.is_empty());
+    Some(rows)
+}
+
+/// Write `rows` as csv, padding short rows so every row has the same width.
+pub fn to_csv(rows: &[Vec<String>]) -> csv::Result<Vec<u8>> {
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        let padding = std::iter::repeat_n("", width - row.len());
+        writer.write_record(row.iter().map(|s| s.as_str()).chain(padding))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(std::io::Error::other(e.to_string())))
+}
+
+fn find_tag(lower: &str, from: usize, name: &str) -> Option<usize> {
+    let open = format!("<{}", name);
+    let mut pos = from;
+    while let Some(i) = lower[pos..].find(&open) {
+        let at = pos + i;
+        // `<table>` or `<table ...>` but not `<tablefoo>`
+        match lower[at + open.len()..].chars().next() {
+            Some(c) if c == '>' || c.is_ascii_whitespace() => return Some(at),
+            _ => pos = at + open.len(),
+        }
+    }
+    None
+}
+
+fn end_cell(rows: &mut [Vec<String>], cell: &mut Option<String>, colspan: usize) {
+    if let (Some(text), Some(row)) = (cell.take(), rows.last_mut()) {
+        row.push(clean_text(&text));
+        row.extend(std::iter::repeat_n(String::new(), colspan - 1));
+    }
+}
+
+fn push_space(cell: &mut Option<String>) {
+    if let Some(text) = cell.as_mut() {
+        text.push(' ');
+    }
+}
+
+// the value of `name="..."`, `name='...'` or `name=...` in a lowercased tag
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let i = tag.find(&format!("{}=", name))?;
+    let value = &tag[i + name.len() + 1..];
+    let value = match value.chars().next()? {
+        q @ ('"' | '\'') => value[1..].split(q).next()?,
+        _ => value.split(|c: char| c.is_ascii_whitespace()).next()?,
+    };
+    Some(value.trim())
+}
+
+// decode entities and collapse whitespace
+fn clean_text(text: &str) -> String {
+    decode_entities(text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| {
+                let entity = &rest[1..end + 1];
+                let c = match entity {
+                    "amp" => Some('&'),
+                    "lt" => Some('<'),
+                    "gt" => Some('>'),
+                    "quot" => Some('"'),
+                    "apos" => Some('\''),
+                    "nbsp" => Some(' '),
+                    _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+                        u32::from_str_radix(&entity[2..], 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                    }
+                    _ if entity.starts_with('#') => {
+                        entity[1..].parse::<u32>().ok().and_then(char::from_u32)
+                    }
+                    _ => None,
+                };
+                c.map(|c| (c, end + 2))
+            });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_first_table() {
+        let html = r#"<!DOCTYPE html><html><body>
+            <p>Report</p>
+            <TABLE class="data">
+              <thead><tr><th>Name</th><th>Price &amp; tax</th><th>Qty</th></tr></thead>
+              <!-- <tr><td>hidden</td></tr> -->
+              <tr><td><b>Apple</b></td><td>1.50</td><td>3</td></tr>
+              <tr><td colspan="2">Pear<br>(ripe)</td><td>10</td>
+              <tr><td>Fig</td><td><table><tr><td>a</td><td>b</td></tr></table></td></tr>
+            </TABLE>
+            <table><tr><td>second</td></tr></table>
+        </body></html>"#;
+        let rows = first_table(html).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec!["Name", "Price & tax", "Qty"],
+                vec!["Apple", "1.50", "3"],
+                vec!["Pear (ripe)", "", "10"],
+                vec!["Fig", "a b"],
+            ]
+        );
+        assert_eq!(
+            String::from_utf8(to_csv(&rows).unwrap()).unwrap(),
+            "Name,Price & tax,Qty\nApple,1.50,3\nPear (ripe),,10\nFig,a b,\n"
+        );
+        assert!(first_table("<p>no tables here</p>").is_none());
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(
+            decode_entities("&lt;a&gt; &#65;&#x42; &copy; AT&T"),
+            "<a> AB &copy; AT&T"
+        );
+    }
+}
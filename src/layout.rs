@@ -0,0 +1,80 @@
+//! Which columns of a table fit the width of a terminal.
+
+/// The columns of a table that are shown and the ones that are dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    /// The widths of the shown columns, the first columns of the table.
+    pub widths: Vec<usize>,
+    /// The indices of the columns that do not fit, the rest of the table.
+    pub dropped: Vec<usize>,
+}
+
+/// Lays out columns of `widths`, each with the space after it, left to right
+/// after a gutter of `gutter_width`. Columns are shown while the line fits in
+/// `term_width`; the first one that does not fit and every column after it
+/// are dropped.
+///
+/// ```
+/// use tidy_viewer::layout::{fit, Layout};
+///
+/// let layout = fit(&[6, 8, 6, 8], 30, 8);
+/// assert_eq!(layout, Layout { widths: vec![6, 8, 6], dropped: vec![3] });
+/// ```
+pub fn fit(widths: &[usize], term_width: usize, gutter_width: usize) -> Layout {
+    let mut line = gutter_width;
+    let shown = widths
+        .iter()
+        .take_while(|&&width| {
+            line = line.saturating_add(width);
+            line <= term_width
+        })
+        .count();
+    Layout {
+        widths: widths[..shown].to_vec(),
+        dropped: (shown..widths.len()).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn fits_columns_left_to_right() {
+        let layout = |widths: &[usize], term_width, gutter_width| {
+            let Layout { widths, dropped } = fit(widths, term_width, gutter_width);
+            (widths, dropped)
+        };
+        assert_eq!(layout(&[5, 5, 5], 23, 8), (vec![5, 5, 5], vec![]));
+        assert_eq!(layout(&[5, 5, 5], 22, 8), (vec![5, 5], vec![2]));
+        assert_eq!(layout(&[5, 5, 5], 13, 8), (vec![5], vec![1, 2]));
+        assert_eq!(layout(&[5, 5, 5], 12, 8), (vec![], vec![0, 1, 2]));
+        // a narrow column after a wide one that does not fit is dropped too
+        assert_eq!(layout(&[5, 50, 2], 20, 0), (vec![5], vec![1, 2]));
+        assert_eq!(layout(&[5, 5], 10, 0), (vec![5, 5], vec![]));
+        assert_eq!(layout(&[], 80, 8), (vec![], vec![]));
+        assert_eq!(layout(&[5], 80, usize::MAX), (vec![], vec![0]));
+    }
+
+    proptest! {
+        #[test]
+        fn shown_columns_fit(
+            widths in prop::collection::vec(0usize..40, 0..20),
+            term_width in 0usize..200,
+            gutter_width in 0usize..10,
+        ) {
+            let layout = fit(&widths, term_width, gutter_width);
+            prop_assert_eq!(&layout.widths[..], &widths[..layout.widths.len()]);
+            prop_assert_eq!(
+                layout.dropped,
+                (layout.widths.len()..widths.len()).collect::<Vec<_>>()
+            );
+            let line: usize = gutter_width + layout.widths.iter().sum::<usize>();
+            prop_assert!(layout.widths.is_empty() || line <= term_width);
+            if let Some(&next) = widths.get(layout.widths.len()) {
+                prop_assert!(line + next > term_width);
+            }
+        }
+    }
+}
@@ -0,0 +1,4 @@
+//! Column formatting behind Tidy Viewer (tv): type inference, significant figures,
+//! NA handling and decimal alignment, and the layout of columns in a terminal.
+pub mod datatype;
+pub mod layout;
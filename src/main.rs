@@ -1,17 +1,32 @@
-use csv::{Reader, ReaderBuilder};
+use csv::{Reader, StringRecord};
 use owo_colors::OwoColorize;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{self, BufReader, Read};
-use std::path::PathBuf;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 use structopt::StructOpt;
-mod datatype;
-use calm_io::stdout;
-use calm_io::stdoutln;
+use unicode_width::UnicodeWidthStr;
+mod color;
+mod compression;
+mod crash;
+mod expr;
+mod format;
+mod glimpse;
+mod html;
+mod manual;
+mod output;
+mod remote;
+mod render;
+#[cfg(feature = "http")]
+mod update;
 use crossterm::terminal::size;
 use directories::BaseDirs;
+use render::{span, CellStyle};
 use serde::Deserialize;
 use serde::Serialize;
 use std::convert::TryInto;
+use tidy_viewer::{datatype, layout};
 
 #[derive(StructOpt)]
 #[structopt(
@@ -23,40 +38,9 @@ use std::convert::TryInto;
     tv diamonds.csv
 
     Configuration File Support:
-    An example config is printed to make it easy to copy/paste to `tv.toml`.
-    Check the parameters you have changed with `tv --config-details`.
-    The config (tv.toml) location is dependent on OS:
-        * Linux: $XDG_CONFIG_HOME or $HOME/.config/tv.toml
-        * macOS: $HOME/Library/Application Support/tv.toml
-        * Windows: {FOLDERID_RoamingAppData}\\tv.toml
-
-        ## ==Tidy-Viewer Config Example==
-        ## Remove the first column of comments for valid toml file
-        ## All fields must be defined. Partial files will not be read.
-        ## The delimiter separating the columns. [default: ,]
-        #delimiter = \",\"
-        ## Add a title to your tv. Example \'Test Data\' [default: NA (\"\")]
-        #title = \"\"
-        ## Add a footer to your tv. Example \'footer info\' [default: NA (\"\")]
-        #footer = \"\"
-        ## The upper (maximum) width of columns. [default: 20]
-        #upper_column_width = 20
-        ## The minimum width of columns. Must be 2 or larger. [default: 2]
-        #lower_column_width = 2
-        ## head number of rows to output <row-display> [default: 25]
-        #number = 35
-        ## extend width and length in terms of the number of rows and columns displayed beyond term width [default: false]
-        # extend_width_length = true
-        ## meta_color = [R,G,B] color for row index and \"tv dim: rows x cols\"
-        #meta_color = [64, 179, 162]
-        ## header_color = [R,G,B] color for column headers
-        #header_color = [232, 168, 124]
-        ## std_color = [R,G,B] color for standard cell data values
-        #std_color = [133, 205, 202]
-        ## na_color = [R,G,B] color for NA values
-        #na_color = [226, 125, 95]
-        ## neg_num_color = [R,G,B] color for negative values
-        #neg_num_color = [226, 125, 95]
+    Options can also be set in tv.toml. `tv --manual` shows the config file format,
+    where it is on each OS and the color roles. Check the parameters you have
+    changed with `tv --config-details`.
 "
 )]
 struct Cli {
@@ -84,6 +68,11 @@ struct Cli {
         help = "Jump over (skip) invalid rows in the file. This includes rows with the incorrect number of columns."
     )]
     skip_invalid_rows: bool,
+    #[structopt(
+        long = "verbose-footer",
+        help = "Below the table, also tell how many rows -j skipped and the lines of the file they are on."
+    )]
+    verbose_footer: bool,
     #[structopt(
         short = "p",
         long = "pedantic",
@@ -107,44 +96,213 @@ struct Cli {
     #[structopt(
         short = "n",
         long = "number-of-rows-to-output",
-        default_value = "25",
-        help = "Show how many rows to display."
+        help = "Show how many rows to display. Defaults to 25."
     )]
-    row_display: usize,
+    row_display: Option<usize>,
     #[structopt(
         short = "l",
         long = "lower-column-width",
-        default_value = "2",
-        help = "The lower (minimum) width of columns. Must be 2 or larger."
+        help = "The lower (minimum) width of columns. Must be 2 or larger. Defaults to 2."
     )]
-    lower_column_width: usize,
+    lower_column_width: Option<usize>,
     #[structopt(
         short = "u",
         long = "upper-column-width",
-        default_value = "20",
-        help = "The upper (maximum) width of columns."
+        help = "The upper (maximum) width of columns. Defaults to 20."
     )]
-    upper_column_width: usize,
+    upper_column_width: Option<usize>,
     #[structopt(
         short = "s",
         long = "delimiter",
         parse(try_from_str = datatype::parse_delimiter),
-        help = "The delimiter separating the columns."
+        help = "The delimiter separating the columns. Overrides the one of the file extension. One of comma, tab, pipe, semicolon or space, or characters that may be several (||) and use the escapes \\t and \\xNN (\\x01)."
     )]
-    delimiter: Option<u8>,
+    delimiter: Option<Box<[u8]>>,
     #[structopt(
         short = "g",
         long = "sigfig",
-        default_value = "3",
-        help = "Significant Digits. Default 3. Max is 7"
+        parse(try_from_str = datatype::parse_sigfig),
+        help = "Significant Digits. Default 3. From 1 to 15"
+    )]
+    sigfig: Option<i64>,
+    #[structopt(
+        long = "integer-sigfig",
+        default_value = "full",
+        help = "How to show numbers with more whole digits than sigfig. One of full (12345), rounded (12300~, ~ marks rounded values) or compact (12.3K)"
+    )]
+    integer_sigfig: datatype::IntegerMode,
+    #[structopt(
+        long = "radix",
+        default_value = "keep",
+        help = "How to show hex (0x1A), binary (0b10) and octal (0o17) integers. One of keep (as written, right aligned) or dec (converted to decimal)"
+    )]
+    radix: datatype::Radix,
+    #[structopt(
+        long = "literal-na",
+        help = "Show NA-like strings (NA, null, None, n/a, ...) as written. Only empty fields are treated as missing, and they are left blank."
+    )]
+    literal_na: bool,
+    #[structopt(
+        long = "locale",
+        number_of_values = 1,
+        help = "Also read the missing value and logical words of a language, like k.A. and WAHR/FALSCH for de. One of de, es, fr, it or pt. May be repeated."
+    )]
+    locale: Vec<datatype::Locale>,
+    #[structopt(
+        long = "na-preset",
+        help = "Which strings are missing values: strict (empty, NA and NULL), standard (also NaN, null, None, n/a, missing and the like) or loose (also -, ?, #N/A, nil and other placeholders) [default: standard]"
+    )]
+    na_preset: Option<datatype::NaValues>,
+    #[structopt(
+        long = "na-values",
+        require_delimiter = true,
+        conflicts_with = "na-preset",
+        help = "The strings that are missing values, instead of a preset. A leading comma makes empty fields missing. Example `tv --na-values ,NA,-999 data.csv`"
+    )]
+    na_values: Vec<String>,
+    #[structopt(
+        long = "decimal-comma",
+        help = "Read numbers written with a decimal comma, like 3,14 or 1.234,5, as numbers. Usually with `-s semicolon`. See --show-decimal-comma."
+    )]
+    decimal_comma: bool,
+    #[structopt(
+        long = "show-decimal-comma",
+        help = "Show numbers with a decimal comma, 3,14 rather than 3.14."
+    )]
+    show_decimal_comma: bool,
+    #[structopt(
+        long = "iso-dates",
+        help = "Show the dates of columns holding only dates, like 01/31/2024, 31.01.2024, 20240131 or Jan 31, 2024, as 2024-01-31. Slashes are read month first, dots day first."
+    )]
+    iso_dates: bool,
+    #[structopt(
+        long = "whole-seconds",
+        help = "Show date-times like 2020-10-09T11:59:37.250Z to the second, as 2020-10-09 11:59:37Z. See --tz."
+    )]
+    whole_seconds: bool,
+    #[structopt(
+        long = "tz",
+        help = "Show date-times with a zone, like 2020-10-09T11:59:37Z, in this zone and to the second: local, UTC or an offset like +05:30. Those without a zone are left in theirs. Example `tv --tz local logs.csv`"
+    )]
+    tz: Option<datatype::TimeZone>,
+    #[structopt(
+        long = "epoch",
+        help = "Show the Unix timestamps of columns named like *_ts or *_time, integers of up to 10 digits (seconds) or 13 (milliseconds), as date-times. See --epoch-cols and --tz."
+    )]
+    epoch: bool,
+    #[structopt(
+        long = "epoch-cols",
+        require_delimiter = true,
+        help = "Show the Unix timestamps of these columns, names or 1-based indices, as date-times. Integers of up to 10 digits are seconds, of 13 milliseconds. Example `tv --epoch-cols created,updated events.csv`"
+    )]
+    epoch_cols: Vec<String>,
+    #[structopt(
+        long = "only-na-rows",
+        conflicts_with = "drop-na-rows",
+        help = "Only show rows with missing values. See --na-how."
+    )]
+    only_na_rows: bool,
+    #[structopt(
+        long = "drop-na-rows",
+        help = "Hide rows with missing values. See --na-how."
+    )]
+    drop_na_rows: bool,
+    #[structopt(
+        long = "drop-empty",
+        help = "Hide columns where every value is missing."
+    )]
+    drop_empty: bool,
+    #[structopt(
+        long = "drop-constant",
+        help = "Hide columns where every value is the same, missing values included, like a column that is all NA."
+    )]
+    drop_constant: bool,
+    #[structopt(
+        long = "na-how",
+        default_value = "any",
+        help = "Which rows --only-na-rows and --drop-na-rows act on. One of any (a value is missing) or all (every value is missing)"
     )]
-    sigfig: i64,
+    na_how: NaHow,
     #[structopt(
         short = "e",
         long = "extend-width-and-length",
-        help = "Extended width beyond term width (do not truncate). Useful with `less -S`."
+        help = "Extended width beyond term width (do not truncate). Useful with `less -S`. Same as --all-columns --all-rows."
     )]
     extend_width_length: bool,
+    #[structopt(
+        long = "no-extend-width-and-length",
+        conflicts_with = "extend-width-length",
+        help = "Fit the table to the terminal even when tv.toml sets extend_width_length."
+    )]
+    no_extend_width_length: bool,
+    #[structopt(
+        long = "all-columns",
+        help = "Show every column, wider than the terminal. Rows are still limited by -n. Useful with `less -S`."
+    )]
+    all_columns: bool,
+    #[structopt(
+        long = "all-rows",
+        help = "Show every row unless -n is set, while columns still fit the terminal."
+    )]
+    all_rows: bool,
+    #[structopt(
+        long = "max-cols",
+        help = "Show at most this many columns, even when the terminal fits more. Keeps screenshots and docs the same across terminal widths. Applies to every --output-format."
+    )]
+    max_cols: Option<usize>,
+    #[structopt(
+        long = "glimpse",
+        help = "Show one line per column with its name, type and first values, like dplyr::glimpse(). Useful for data too wide for the terminal."
+    )]
+    glimpse: bool,
+    #[structopt(
+        long = "na-report",
+        conflicts_with = "glimpse",
+        help = "Show one line per column with how many of its values are missing and their percentage of the rows, over the whole file."
+    )]
+    na_report: bool,
+    #[structopt(
+        long = "na-threshold",
+        parse(try_from_str = datatype::parse_percent),
+        help = "Color the names of columns where more than this percentage of the rows shown are missing with the NA color, e.g. 50, to flag mostly empty columns."
+    )]
+    na_threshold: Option<f64>,
+    #[structopt(
+        long = "fit-report",
+        help = "Print to stderr, as JSON, which columns fit the terminal, which were dropped and their widths. For tools that decide whether to rerun with -e."
+    )]
+    fit_report: bool,
+    #[structopt(
+        long = "style",
+        default_value = "default",
+        help = "One of default or diff-friendly. diff-friendly has no color, dimensions, footers or leading newline and shows every column whatever the terminal width, so the output of two versions of a file can be diffed."
+    )]
+    style: Style,
+    #[structopt(
+        long = "preset",
+        help = "Settings for where the output will be pasted, used for the options not given. One of slack (a plain table in a code block, laid out for 80 characters), github (a markdown table of every column) or email (the html page, laid out for 100 characters)."
+    )]
+    preset: Option<Preset>,
+    #[structopt(
+        long = "transpose",
+        help = "Swap rows and columns: each column becomes a row and each record a column, -n picks the records shown. For a single record or a wide configuration table."
+    )]
+    transpose: bool,
+    #[structopt(
+        long = "digest",
+        help = "Print a SHA256 of the shown rows, unformatted but after conversions like --percent or --iso-dates, below the table. Others can check they look at the same slice of data."
+    )]
+    digest: bool,
+    #[structopt(
+        long = "precision-hint",
+        help = "Add a dimmed note below the table naming the columns with numbers rounded to --sigfig, so a screenshot does not pass for full precision."
+    )]
+    precision_hint: bool,
+    #[structopt(
+        long = "highlight",
+        help = "Show the parts of values matching this regular expression in the highlight color of the theme. Example `tv --highlight 'Premium|Ideal' diamonds.csv`"
+    )]
+    highlight: Option<regex::Regex>,
     #[structopt(
         short = "d",
         long = "debug-mode",
@@ -172,6 +330,115 @@ struct Cli {
     )]
     no_row_numbering: bool,
 
+    #[structopt(
+        long = "gutter-width",
+        help = "Width of the row number gutter. 0 removes the gutter. Defaults to 6."
+    )]
+    gutter_width: Option<usize>,
+
+    #[structopt(
+        long = "no-leading-newline",
+        help = "Do not print a blank line before the output"
+    )]
+    no_leading_newline: bool,
+
+    #[structopt(
+        long = "source-lines",
+        help = "Show the line number each row starts on in the source file, next to the row number"
+    )]
+    source_lines: bool,
+
+    #[structopt(
+        long = "comment",
+        parse(try_from_str = datatype::parse_comment),
+        help = "Skip lines starting with this character, like metadata lines at the top of scientific exports. Example `tv --comment '#' data.csv`"
+    )]
+    comment: Option<u8>,
+
+    #[structopt(
+        long = "encoding",
+        parse(try_from_str = format::parse_encoding),
+        help = "The text encoding of the input, like latin1, windows-1252 or utf16. UTF-16 with a byte order mark is detected without it."
+    )]
+    encoding: Option<&'static encoding_rs::Encoding>,
+
+    #[structopt(
+        long = "skip-rows",
+        default_value = "0",
+        help = "Skip the first rows of the file, like a title banner or notes above the header"
+    )]
+    skip_rows: usize,
+
+    #[structopt(
+        long = "offset",
+        default_value = "0",
+        help = "Start the display after this many data rows, to page through a file in chunks. Example `tv --offset 1000 -n 100 big.csv`"
+    )]
+    offset: usize,
+
+    #[structopt(
+        long = "tail",
+        conflicts_with_all = &["offset", "force-all-rows"],
+        help = "Show the last rows of the file instead of the first. Example `tv --tail 10 log.csv`"
+    )]
+    tail: Option<usize>,
+
+    #[structopt(
+        long = "range",
+        conflicts_with_all = &["offset", "force-all-rows", "tail"],
+        help = "Show the rows numbered from START to END, inclusive, where either end may be left open. Example `tv --range 100:150 big.csv`"
+    )]
+    range: Option<RowRange>,
+
+    #[structopt(
+        long = "header-row",
+        conflicts_with = "skip-rows",
+        help = "The row the header is on, counting from 1 without blank lines. The rows above it are skipped. Example `tv --header-row 3 report.csv`"
+    )]
+    header_row: Option<usize>,
+
+    #[structopt(
+        long = "no-header",
+        conflicts_with = "header-row",
+        help = "The first row is data, not column names. The columns are named X1, X2, ..."
+    )]
+    no_header: bool,
+
+    #[structopt(
+        long = "output-format",
+        help = "What to print: table (for the terminal), html (a standalone page with the theme's colors, for reports and emails), json (every row as an object with typed values), latex (a booktabs tabular for papers), csv (the rows and columns the table would show, unformatted, after conversions like --percent or --iso-dates), tsv (the same, separated by tabs) or markdown (a GitHub flavored table). Defaults to table."
+    )]
+    output_format: Option<output::OutputFormat>,
+
+    #[structopt(
+        long = "output-delimiter",
+        parse(try_from_str = output::parse_output_delimiter),
+        help = "The delimiter of csv and tsv output, one byte given like --delimiter. Example `tv -s semicolon --output-format csv --output-delimiter comma european.csv` [default: comma for csv, tab for tsv]"
+    )]
+    output_delimiter: Option<u8>,
+
+    #[structopt(
+        long = "quote",
+        default_value = "necessary",
+        help = "Which fields of csv and tsv output are quoted: necessary (those with a delimiter, quote or line break), always, non-numeric or never"
+    )]
+    quote: output::Quote,
+
+    #[structopt(
+        short = "o",
+        long = "output",
+        parse(from_os_str),
+        help = "Write the output to this file instead of stdout. The table is written without color unless -a is given."
+    )]
+    output: Option<PathBuf>,
+
+    #[structopt(
+        long = "clipboard",
+        conflicts_with = "FILE",
+        help = "Read the table from the system clipboard, like cells copied from a spreadsheet"
+    )]
+    clipboard: bool,
+
     #[structopt(
         short = "C",
         long = "config-details",
@@ -179,28 +446,205 @@ struct Cli {
     )]
     config_details: bool,
 
-    #[structopt(name = "FILE", parse(from_os_str), help = "File to process")]
-    file: Option<PathBuf>,
+    #[structopt(
+        long = "manual",
+        help = "Show the manual, the options with the config file format, color roles and examples, in a pager."
+    )]
+    manual: bool,
+
+    #[structopt(
+        long = "man-page",
+        conflicts_with = "manual",
+        help = "Print the manual as a man page, like `tv --man-page > tv.1`."
+    )]
+    man_page: bool,
+
+    #[cfg(feature = "http")]
+    #[structopt(
+        long = "check-update",
+        help = "Tell whether a newer release of tv is on GitHub."
+    )]
+    check_update: bool,
+
+    #[cfg(feature = "self-update")]
+    #[structopt(
+        long = "self-update",
+        conflicts_with = "check-update",
+        help = "Install the latest release of tv from GitHub in place of this one."
+    )]
+    self_update: bool,
+
+    #[structopt(
+        long = "color-depth",
+        help = "Color escape codes to use: truecolor or 16. Detected from $COLORTERM and $TERM when not given."
+    )]
+    color_depth: Option<color::ColorDepth>,
+
+    #[structopt(
+        short = "m",
+        long = "mutate",
+        parse(try_from_str = expr::parse_mutation),
+        number_of_values = 1,
+        help = "Add a computed column from an arithmetic expression of other columns. May be repeated. Example `tv --mutate 'margin = (revenue - cost)/revenue' sales.csv`"
+    )]
+    mutate: Vec<expr::Mutation>,
+
+    #[structopt(
+        long = "select",
+        require_delimiter = true,
+        help = "Only show these columns, in this order, by name or 1-based index. Example `tv --select cut,price,1 diamonds.csv`"
+    )]
+    select: Vec<String>,
+
+    #[structopt(
+        long = "drop",
+        require_delimiter = true,
+        conflicts_with = "select",
+        help = "Hide these columns, by name or 1-based index, like wide free text. Example `tv --drop Name,Ticket titanic.csv`"
+    )]
+    drop: Vec<String>,
+
+    #[structopt(
+        long = "select-regex",
+        conflicts_with_all = &["select", "drop"],
+        help = "Only show the columns whose names match this regular expression, in file order. Example `tv --select-regex '^sales_' wide.csv`"
+    )]
+    select_regex: Option<regex::Regex>,
+
+    #[structopt(
+        long = "sort",
+        help = "Sort the rows by this column, a name or 1-based index, smallest first or, with :desc, largest first. Number columns are sorted as numbers and others as text, with missing values last. Example `tv --sort price:desc diamonds.csv`"
+    )]
+    sort: Option<SortKey>,
+
+    #[structopt(
+        long = "unique",
+        help = "Only show the first of rows that are the same, and how many were removed below the table. Example `tv --unique orders.csv`"
+    )]
+    unique: bool,
+
+    #[structopt(
+        long = "unique-by",
+        require_delimiter = true,
+        help = "Like --unique, but rows are the same when these columns, names or 1-based indices, are. Example `tv --unique-by cut,color diamonds.csv`"
+    )]
+    unique_by: Vec<String>,
+
+    #[structopt(
+        long = "percent",
+        require_delimiter = true,
+        help = "Show the proportions of these columns, names or 1-based indices, as percentages: 0.153 as 15.3%. Example `tv --percent share,growth sales.csv`"
+    )]
+    percent: Vec<String>,
+
+    #[structopt(
+        long = "count",
+        help = "Show how often each value of this column, a name or 1-based index, occurs, most frequent first, with its percentage of the rows. Example `tv --count cut diamonds.csv`"
+    )]
+    count: Option<String>,
+
+    #[structopt(
+        name = "FILE",
+        parse(from_os_str),
+        help = "File(s) to process. May also be an http(s)://, s3:// or gs:// URL. Several files, or a glob like 'data/*.csv', are shown as one table with a file column when their columns match. For an HTML page the first <table> is shown."
+    )]
+    files: Vec<PathBuf>,
 }
 
-fn main() {
-    // toml struct
-    #[derive(Deserialize, Serialize, Debug, Clone)]
-    struct Config {
-        delimiter: Option<String>,
-        title: Option<String>,
-        footer: Option<String>,
-        upper_column_width: Option<usize>,
-        lower_column_width: Option<usize>,
-        number: Option<usize>,
-        extend_width_length: Option<bool>,
-        meta_color: Option<toml::value::Array>,
-        header_color: Option<toml::value::Array>,
-        std_color: Option<toml::value::Array>,
-        na_color: Option<toml::value::Array>,
-        neg_num_color: Option<toml::value::Array>,
+// toml struct
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+struct Config {
+    delimiter: Option<String>,
+    title: Option<String>,
+    footer: Option<String>,
+    upper_column_width: Option<usize>,
+    lower_column_width: Option<usize>,
+    number: Option<usize>,
+    extend_width_length: Option<bool>,
+    meta_color: Option<toml::value::Array>,
+    header_color: Option<toml::value::Array>,
+    std_color: Option<toml::value::Array>,
+    na_color: Option<toml::value::Array>,
+    neg_num_color: Option<toml::value::Array>,
+    highlight_color: Option<toml::value::Array>,
+    gutter_width: Option<usize>,
+    leading_newline: Option<bool>,
+    comment: Option<String>,
+    na_values: Option<Vec<String>>,
+}
+
+// The options that can be set both in tv.toml and on the command line.
+#[derive(Debug, PartialEq)]
+struct Options {
+    extend_width_length: bool,
+    gutter_width: usize,
+    leading_newline: bool,
+    title: String,
+    footer: String,
+    row_display: usize,
+    lower_column_width: usize,
+    upper_column_width: usize,
+}
+
+// The options below all follow the same logic:
+//   If the user provides a config file and no cli argument, use the config file
+//   If the user provides a cli argument, override the config file
+//   If the user provides no cli argument, use the config file
+//   If the user provides no cli argument and no config file, use the default value
+// A cli argument counts as provided when it is given, even with the default
+// value. The title and footer count as provided when they are not "NA".
+fn resolve_options(config: &Config, opt: &Cli) -> Options {
+    let is_title_defined: bool = opt.title != "NA";
+    let is_footer_defined: bool = opt.footer != "NA";
+
+    let extend_width_length: bool = match (config.extend_width_length, opt.extend_width_length) {
+        (_, true) => true,
+        (Some(x), false) => x && !opt.no_extend_width_length,
+        (None, false) => false,
+    };
+    let gutter_width: usize = opt.gutter_width.or(config.gutter_width).unwrap_or(6);
+    let leading_newline: bool = match (config.leading_newline, opt.no_leading_newline) {
+        (Some(x), false) => x,
+        (Some(_x), true) => false,
+        (None, false) => true,
+        (None, true) => false,
+    };
+    let title: &String = match (&config.title, &is_title_defined) {
+        (Some(ref x), false) => x,
+        (Some(_x), true) => &opt.title,
+        (None, false) => &opt.title,
+        (None, true) => &opt.title,
+    };
+    let footer: &String = match (&config.footer, &is_footer_defined) {
+        (Some(ref x), false) => x,
+        (Some(_x), true) => &opt.footer,
+        (None, false) => &opt.footer,
+        (None, true) => &opt.footer,
+    };
+    let row_display: usize = opt.row_display.or(config.number).unwrap_or(25);
+    let lower_column_width: usize = opt
+        .lower_column_width
+        .or(config.lower_column_width)
+        .unwrap_or(2);
+    let upper_column_width: usize = opt
+        .upper_column_width
+        .or(config.upper_column_width)
+        .unwrap_or(20);
+
+    Options {
+        extend_width_length,
+        gutter_width,
+        leading_newline,
+        title: title.clone(),
+        footer: footer.clone(),
+        row_display,
+        lower_column_width,
+        upper_column_width,
     }
+}
 
+fn main() {
+    crash::install();
     let base_dir: Option<BaseDirs> = BaseDirs::new();
     let config_base_dir: BaseDirs = base_dir.unwrap();
     let config_dir = config_base_dir.config_dir();
@@ -224,254 +668,360 @@ fn main() {
             std_color: None,
             na_color: None,
             neg_num_color: None,
+            highlight_color: None,
+            gutter_width: None,
+            leading_newline: None,
+            comment: None,
+            na_values: None,
         },
     };
     // load cli args
-    let opt = Cli::from_args();
+    let mut opt = Cli::from_args();
+    #[cfg(feature = "http")]
+    if opt.check_update {
+        check_update();
+        return;
+    }
+    #[cfg(feature = "self-update")]
+    if opt.self_update {
+        self_update();
+        return;
+    }
+    if opt.manual || opt.man_page {
+        // the help without line wrapping, each option on one line
+        let mut help = Vec::new();
+        let _ = Cli::clap().set_term_width(0).write_long_help(&mut help);
+        let help = String::from_utf8_lossy(&help);
+        let version = env!("CARGO_PKG_VERSION");
+        if opt.man_page {
+            print!("{}", manual::man_page(&help, version));
+        } else {
+            let width = size().map_or(80, |(w, _)| w as usize).min(100);
+            show_in_pager(&manual::text(&help, version, width));
+        }
+        return;
+    }
+    if let Some(preset) = opt.preset {
+        preset.apply(&mut opt);
+    }
+    let output_format = opt.output_format.unwrap_or(output::OutputFormat::Table);
+    // the NA values of the cli take precedence over the ones of tv.toml
+    let na_values = if !opt.na_values.is_empty() {
+        datatype::NaValues::List(opt.na_values.clone())
+    } else if let Some(preset) = &opt.na_preset {
+        preset.clone()
+    } else if let Some(values) = &config.na_values {
+        datatype::NaValues::List(values.clone())
+    } else {
+        datatype::NaValues::default()
+    };
+    // which values are missing, for the passes before the table is formatted
+    let missing_options = datatype::FormatOptions {
+        na_strings: !opt.literal_na,
+        na_values,
+        locales: opt.locale.clone(),
+        ..Default::default()
+    };
 
     // print helpful config details
-    match opt.config_details {
-        true => {
-            println!("");
-            println!("{:}", "tv.toml".to_string().truecolor(94, 129, 172));
-            match config.clone().delimiter {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " delimiter = ".to_string().truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106),                // red
-                    " delimiter = None".truecolor(216, 222, 233)  // white
-                ),
-            }
-            // match title
-            match config.clone().title {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " title = ".to_string().truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106),            // red
-                    " title = None".truecolor(216, 222, 233)  // white
-                ),
-            }
+    if opt.config_details {
+        println!();
+        println!("{:}", "tv.toml".to_string().truecolor(94, 129, 172));
+        match config.clone().delimiter {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " delimiter = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),                // red
+                " delimiter = None".truecolor(216, 222, 233)  // white
+            ),
+        }
+        // match title
+        match config.clone().title {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " title = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),            // red
+                " title = None".truecolor(216, 222, 233)  // white
+            ),
+        }
 
-            // match footer
-            match config.clone().footer {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " footer = ".to_string().truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106),             // red
-                    " footer = None".truecolor(216, 222, 233)  // white
-                ),
-            }
+        // match footer
+        match config.clone().footer {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " footer = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),             // red
+                " footer = None".truecolor(216, 222, 233)  // white
+            ),
+        }
 
-            // match upper_column_width
-            match config.clone().upper_column_width {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " upper_column_width = "
-                        .to_string()
-                        .truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106), // red
-                    " upper_column_width = None".truecolor(216, 222, 233)  // white
-                ),
-            }
+        // match upper_column_width
+        match config.clone().upper_column_width {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " upper_column_width = "
+                    .to_string()
+                    .truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106), // red
+                " upper_column_width = None".truecolor(216, 222, 233)  // white
+            ),
+        }
 
-            // match lower_column_width
-            match config.clone().lower_column_width {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " lower_column_width = "
-                        .to_string()
-                        .truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106), // red
-                    " lower_column_width = None".truecolor(216, 222, 233)  // white
-                ),
-            }
+        // match lower_column_width
+        match config.clone().lower_column_width {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " lower_column_width = "
+                    .to_string()
+                    .truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106), // red
+                " lower_column_width = None".truecolor(216, 222, 233)  // white
+            ),
+        }
 
-            // match number
-            match config.clone().number {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " number = ".to_string().truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106),             // red
-                    " number = None".truecolor(216, 222, 233)  // white
-                ),
-            }
+        // match number
+        match config.clone().number {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " number = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),             // red
+                " number = None".truecolor(216, 222, 233)  // white
+            ),
+        }
 
-            // match extend_width_length
-            match config.clone().extend_width_length {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " extend_width_length = "
-                        .to_string()
-                        .truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106), // red
-                    " extend_width_length = None".truecolor(216, 222, 233)  // white
-                ),
-            }
-            // match meta_color
-            match config.clone().meta_color {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " meta_color = ".to_string().truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106), // red
-                    " meta_color = None".truecolor(216, 222, 233)  // white
-                ),
-            }
+        // match extend_width_length
+        match config.clone().extend_width_length {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " extend_width_length = "
+                    .to_string()
+                    .truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106), // red
+                " extend_width_length = None".truecolor(216, 222, 233)  // white
+            ),
+        }
+        // match gutter_width
+        match config.clone().gutter_width {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " gutter_width = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),                   // red
+                " gutter_width = None".truecolor(216, 222, 233)  // white
+            ),
+        }
 
-            // match header_color
-            match config.clone().header_color {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " header_color = ".to_string().truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106), // red
-                    " header_color = None".truecolor(216, 222, 233)  // white
-                ),
-            }
+        // match leading_newline
+        match config.clone().leading_newline {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " leading_newline = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106), // red
+                " leading_newline = None".truecolor(216, 222, 233)  // white
+            ),
+        }
 
-            // match std_color
-            match config.clone().std_color {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " std_color = ".to_string().truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106),                // red
-                    " std_color = None".truecolor(216, 222, 233)  // white
-                ),
-            }
+        // match comment
+        match config.clone().comment {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " comment = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),              // red
+                " comment = None".truecolor(216, 222, 233)  // white
+            ),
+        }
+        // match na_values
+        match config.clone().na_values {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " na_values = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),                // red
+                " na_values = None".truecolor(216, 222, 233)  // white
+            ),
+        }
+        // match meta_color
+        match config.clone().meta_color {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " meta_color = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),                 // red
+                " meta_color = None".truecolor(216, 222, 233)  // white
+            ),
+        }
 
-            // match na_color
-            match config.clone().na_color {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " na_color = ".to_string().truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106),               // red
-                    " na_color = None".truecolor(216, 222, 233)  // white
-                ),
-            }
+        // match header_color
+        match config.clone().header_color {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " header_color = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),                   // red
+                " header_color = None".truecolor(216, 222, 233)  // white
+            ),
+        }
 
-            // match neg_num_color
-            match config.clone().neg_num_color {
-                Some(x) => println!(
-                    "{}{}{:?}",
-                    "[+]".to_string().truecolor(143, 188, 187), // green
-                    " neg_num_color = ".to_string().truecolor(216, 222, 233), // white
-                    x.truecolor(216, 222, 233)                  // white
-                ),
-                None => println!(
-                    "{}{}",
-                    "[-]".truecolor(191, 97, 106), // red
-                    " neg_num_color = None".truecolor(216, 222, 233)  // white
-                ),
-            }
+        // match std_color
+        match config.clone().std_color {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " std_color = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),                // red
+                " std_color = None".truecolor(216, 222, 233)  // white
+            ),
+        }
+
+        // match na_color
+        match config.clone().na_color {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " na_color = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),               // red
+                " na_color = None".truecolor(216, 222, 233)  // white
+            ),
+        }
+
+        // match neg_num_color
+        match config.clone().neg_num_color {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " neg_num_color = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106),                    // red
+                " neg_num_color = None".truecolor(216, 222, 233)  // white
+            ),
+        }
 
-            std::process::exit(0);
+        // match highlight_color
+        match config.clone().highlight_color {
+            Some(x) => println!(
+                "{}{}{:?}",
+                "[+]".to_string().truecolor(143, 188, 187), // green
+                " highlight_color = ".to_string().truecolor(216, 222, 233), // white
+                x.truecolor(216, 222, 233)                  // white
+            ),
+            None => println!(
+                "{}{}",
+                "[-]".truecolor(191, 97, 106), // red
+                " highlight_color = None".truecolor(216, 222, 233)  // white
+            ),
         }
-        false => {}
+
+        std::process::exit(0);
     }
 
-    let term_tuple: (u16, u16) = size().unwrap();
+    let mut term_tuple: (u16, u16) = terminal_size(
+        size(),
+        std::env::var("COLUMNS").ok(),
+        std::env::var("LINES").ok(),
+    );
+    // a preset lays the table out for the width of where it is pasted
+    if let Some(width) = opt.preset.and_then(Preset::width) {
+        term_tuple.0 = width;
+    }
     let color_option = opt.color;
-    let sigfig: i64 = if opt.sigfig >= 3 && opt.sigfig <= 7 {
-        opt.sigfig
-    } else {
-        panic!("sigfig range must be between 3 and 7")
-    };
+    let sigfig: i64 = opt.sigfig.unwrap_or(3);
     let debug_mode: bool = opt.debug_mode;
-    let is_title_defined: bool = opt.title.chars().count() > 0;
-    let is_footer_defined: bool = opt.title.chars().count() > 0;
-    let is_row_display_defined: bool = opt.row_display != 25;
-    let is_tty: bool = atty::is(atty::Stream::Stdout);
-    let is_force_color: bool = opt.force_color;
+    let is_row_display_defined: bool = opt.row_display.is_some();
+    // consoles that can not show escape codes (old Windows consoles) get plain text
+    // a file written with --output gets plain text unless -a is given
+    let is_diff_friendly: bool = opt.style == Style::DiffFriendly;
+    let is_tty: bool = opt.output.is_none()
+        && !is_diff_friendly
+        && atty::is(atty::Stream::Stdout)
+        && color::ansi_supported();
+    let is_force_color: bool = opt.force_color && !is_diff_friendly;
     let is_no_dimensions: bool = opt.no_dimensions;
     let is_no_row_numbering: bool = opt.no_row_numbering;
     let is_force_all_rows: bool = opt.force_all_rows;
-    let is_extend_width_length: bool = opt.extend_width_length;
-
-    // The options below all follow the same logic:
-    //   If the user provides a config file and no cli argument, use the config file
-    //   If the user provides a cli argument, override the config file
-    //   If the user provides no cli argument, use the config file
-    //   If the user provides no cli argument and no config file, use the default value
-    let extend_width_length_option: bool =
-        match (config.extend_width_length, is_extend_width_length) {
-            (Some(x), false) => x,
-            (Some(_x), true) => opt.extend_width_length,
-            (None, false) => opt.extend_width_length,
-            (None, true) => opt.extend_width_length,
-        };
-    let title_option: &String = match (&config.title, &is_title_defined) {
-        (Some(ref x), false) => &x,
-        (Some(_x), true) => &opt.title,
-        (None, false) => &opt.title,
-        (None, true) => &opt.title,
-    };
-
-    let footer_option: &String = match (&config.footer, &is_footer_defined) {
-        (Some(ref x), false) => &x,
-        (Some(_x), true) => &opt.footer,
-        (None, false) => &opt.footer,
-        (None, true) => &opt.footer,
-    };
 
-    let row_display_option: &usize = match (&config.number, &is_footer_defined) {
-        (Some(ref x), false) => x,
-        (Some(_x), true) => &opt.row_display,
-        (None, false) => &opt.row_display,
-        (None, true) => &opt.row_display,
-    };
+    let options = resolve_options(&config, &opt);
+    let extend_width_length_option: bool = options.extend_width_length;
+    let is_all_columns: bool = extend_width_length_option || opt.all_columns || is_diff_friendly;
+    let is_all_rows: bool = extend_width_length_option || opt.all_rows;
+    let gutter_width: usize = options.gutter_width;
+    // a zero width gutter has no room for row numbers
+    let is_no_row_numbering: bool = is_no_row_numbering || gutter_width == 0;
+    let leading_newline: bool = options.leading_newline && !is_diff_friendly;
+    let title_option: &String = &options.title;
+    let footer_option: &String = &options.footer;
+    let row_display_option: &usize = &options.row_display;
 
     // nord
     let nord_meta_color: [u8; 3] = [143, 188, 187];
@@ -479,104 +1029,100 @@ fn main() {
     let nord_std_color: [u8; 3] = [216, 222, 233];
     let nord_na_color: [u8; 3] = [191, 97, 106];
     let nord_neg_num_color: [u8; 3] = [208, 135, 112];
+    let nord_highlight_color: [u8; 3] = [235, 203, 139];
     // one dark
     let one_dark_meta_color: [u8; 3] = [152, 195, 121];
     let one_dark_header_color: [u8; 3] = [97, 175, 239];
     let one_dark_std_color: [u8; 3] = [171, 178, 191];
     let one_dark_na_color: [u8; 3] = [224, 108, 117];
     let one_dark_neg_num_color: [u8; 3] = [229, 192, 123];
+    let one_dark_highlight_color: [u8; 3] = [198, 120, 221];
     //// gruv
     let gruvbox_meta_color: [u8; 3] = [184, 187, 38];
     let gruvbox_header_color: [u8; 3] = [215, 153, 33];
     let gruvbox_std_color: [u8; 3] = [235, 219, 178];
     let gruvbox_na_color: [u8; 3] = [204, 36, 29];
     let gruvbox_neg_num_color: [u8; 3] = [251, 73, 52];
+    let gruvbox_highlight_color: [u8; 3] = [250, 189, 47];
     //// dracula
     let dracula_meta_color: [u8; 3] = [98, 114, 164];
     let dracula_header_color: [u8; 3] = [80, 250, 123];
     let dracula_std_color: [u8; 3] = [248, 248, 242];
     let dracula_na_color: [u8; 3] = [255, 121, 198];
     let dracula_neg_num_color: [u8; 3] = [188, 63, 60];
+    let dracula_highlight_color: [u8; 3] = [241, 250, 140];
     //// solarized light
     let solarized_meta_color: [u8; 3] = [108, 113, 193];
     let solarized_header_color: [u8; 3] = [88, 110, 117];
     let solarized_std_color: [u8; 3] = [131, 148, 150];
     let solarized_na_color: [u8; 3] = [220, 50, 47];
     let solarized_neg_num_color: [u8; 3] = [42, 161, 152];
+    let solarized_highlight_color: [u8; 3] = [211, 54, 130];
 
     // user args
-    let lower_column_width_defined: bool = opt.lower_column_width != 2;
-    let upper_column_width_defined: bool = opt.lower_column_width != 20;
-    let lower_column_width: usize = match (&config.lower_column_width, &lower_column_width_defined)
-    {
-        (Some(ref x), false) => *x,
-        (Some(_x), true) => opt.lower_column_width,
-        (None, false) => opt.lower_column_width,
-        (None, true) => opt.lower_column_width,
-    };
-    let lower_column_width: usize = if lower_column_width < 2 {
-        panic!("lower-column-width must be larger than 2")
-    } else {
-        lower_column_width
-    };
+    let lower_column_width: usize = options.lower_column_width;
+    if lower_column_width < 2 {
+        eprintln!("lower-column-width must be larger than 2");
+        std::process::exit(1);
+    }
 
-    let upper_column_width: usize = match (&config.upper_column_width, &upper_column_width_defined)
-    {
-        (Some(ref x), false) => *x,
-        (Some(_x), true) => opt.upper_column_width,
-        (None, false) => opt.upper_column_width,
-        (None, true) => opt.upper_column_width,
-    };
-    let upper_column_width = if upper_column_width <= lower_column_width {
-        panic!("upper-column-width must be larger than lower-column-width")
-    } else {
-        upper_column_width
-    };
+    let upper_column_width: usize = options.upper_column_width;
+    if upper_column_width <= lower_column_width {
+        eprintln!("upper-column-width must be larger than lower-column-width");
+        std::process::exit(1);
+    }
     // logic for picking colors given config and user arguments
-    let (meta_color, header_color, std_color, na_color, neg_num_color) = match color_option {
-        1 => (
-            nord_meta_color,
-            nord_header_color,
-            nord_std_color,
-            nord_na_color,
-            nord_neg_num_color,
-        ),
-        2 => (
-            one_dark_meta_color,
-            one_dark_header_color,
-            one_dark_std_color,
-            one_dark_na_color,
-            one_dark_neg_num_color,
-        ),
-        3 => (
-            gruvbox_meta_color,
-            gruvbox_header_color,
-            gruvbox_std_color,
-            gruvbox_na_color,
-            gruvbox_neg_num_color,
-        ),
-        4 => (
-            dracula_meta_color,
-            dracula_header_color,
-            dracula_std_color,
-            dracula_na_color,
-            dracula_neg_num_color,
-        ),
-        5 => (
-            solarized_meta_color,
-            solarized_header_color,
-            solarized_std_color,
-            solarized_na_color,
-            solarized_neg_num_color,
-        ),
-        _ => (
-            nord_meta_color,
-            nord_header_color,
-            nord_std_color,
-            nord_na_color,
-            nord_neg_num_color,
-        ),
-    };
+    let (meta_color, header_color, std_color, na_color, neg_num_color, highlight_color) =
+        match color_option {
+            1 => (
+                nord_meta_color,
+                nord_header_color,
+                nord_std_color,
+                nord_na_color,
+                nord_neg_num_color,
+                nord_highlight_color,
+            ),
+            2 => (
+                one_dark_meta_color,
+                one_dark_header_color,
+                one_dark_std_color,
+                one_dark_na_color,
+                one_dark_neg_num_color,
+                one_dark_highlight_color,
+            ),
+            3 => (
+                gruvbox_meta_color,
+                gruvbox_header_color,
+                gruvbox_std_color,
+                gruvbox_na_color,
+                gruvbox_neg_num_color,
+                gruvbox_highlight_color,
+            ),
+            4 => (
+                dracula_meta_color,
+                dracula_header_color,
+                dracula_std_color,
+                dracula_na_color,
+                dracula_neg_num_color,
+                dracula_highlight_color,
+            ),
+            5 => (
+                solarized_meta_color,
+                solarized_header_color,
+                solarized_std_color,
+                solarized_na_color,
+                solarized_neg_num_color,
+                solarized_highlight_color,
+            ),
+            _ => (
+                nord_meta_color,
+                nord_header_color,
+                nord_std_color,
+                nord_na_color,
+                nord_neg_num_color,
+                nord_highlight_color,
+            ),
+        };
     let is_color_defined = opt.color > 0;
 
     let meta_color = match (&config.meta_color, &is_color_defined) {
@@ -609,6 +1155,33 @@ fn main() {
         (None, false) => nord_neg_num_color,
         (None, true) => neg_num_color,
     };
+    let highlight_color = match (&config.highlight_color, &is_color_defined) {
+        (Some(x), false) => get_color_from_config(&x.clone()),
+        (Some(_x), true) => highlight_color,
+        (None, false) => nord_highlight_color,
+        (None, true) => highlight_color,
+    };
+    // palettes are RGB, terminals without truecolor get the nearest ANSI color
+    let color_depth = opt.color_depth.unwrap_or_else(|| {
+        color::detect_depth(
+            std::env::var("COLORTERM").ok().as_deref(),
+            std::env::var("TERM").ok().as_deref(),
+        )
+    });
+    // the theme as RGB for other output formats
+    let palette = output::Palette {
+        meta: meta_color,
+        header: header_color,
+        std: std_color,
+        na: na_color,
+        neg_num: neg_num_color,
+    };
+    let meta_color = color::to_dyn(meta_color, color_depth);
+    let header_color = color::to_dyn(header_color, color_depth);
+    let std_color = color::to_dyn(std_color, color_depth);
+    let na_color = color::to_dyn(na_color, color_depth);
+    let neg_num_color = color::to_dyn(neg_num_color, color_depth);
+    let highlight_color = color::to_dyn(highlight_color, color_depth);
     // let meta_color = match (&config, is_color_defined) {
     //     (Some(x), false) => get_color_from_config(&x.clone().meta_color),
     //     (Some(_x), true) => meta_color,
@@ -641,34 +1214,88 @@ fn main() {
     // };
 
     //   colname reader
-    let reader_result = build_reader(&opt);
-    let mut r = if let Ok(reader) = reader_result {
-        reader
-    } else {
-        // We can safely use unwrap, because if file in case when file is None
-        // build_reader would return reader created from stdin
-        let path_buf = opt.file.unwrap();
-        let path = path_buf.as_path();
-        if let Some(path) = path.to_str() {
-            eprintln!("Failed to open file: {}", path);
-        } else {
-            eprintln!("Failed to open file.")
-        }
-        return;
-    };
+    // the comment character of the cli takes precedence over the one of tv.toml
+    if opt.comment.is_none() {
+        opt.comment = config
+            .comment
+            .as_deref()
+            .and_then(|c| datatype::parse_comment(c).ok());
+    }
 
-    let rdr = r.records().collect::<Vec<_>>();
-    //.take(row_display_option + 1);
+    // stdin has no extension, so its format and delimiter come from its content.
+    // Peeking fills the stdin buffer without consuming it, so the csv reader
+    // still sees every byte.
+    if opt.files.is_empty() && !opt.clipboard && opt.encoding.is_none() {
+        if let Ok(head) = io::stdin().lock().fill_buf() {
+            if let Some(message) = format::Content::sniff(head).unsupported() {
+                eprintln!("stdin: {}", message);
+                std::process::exit(1);
+            }
+            if opt.delimiter.is_none() {
+                let head = format::skip_lines(head, rows_to_skip(&opt));
+                opt.delimiter = format::sniff_delimiter(head, opt.comment)
+                    .map(|del| Box::new([del]) as Box<[u8]>);
+            }
+        }
+    }
 
-    let rdr = if opt.skip_invalid_rows {
-        rdr.into_iter()
-            .filter_map(|record| record.ok())
-            .collect::<Vec<_>>()
+    // no file means stdin
+    let files = expand_globs(&opt.files);
+    let inputs: Vec<Option<&PathBuf>> = if files.is_empty() {
+        vec![None]
     } else {
-        rdr.into_iter()
-            .map(|record| record.expect("valid csv data"))
-            .collect::<Vec<_>>()
+        files.iter().map(Some).collect()
     };
+    let mut rdr: Vec<StringRecord> = Vec::new();
+    // where the rows skipped by -j are, a line or file:line
+    let mut skipped_rows: Vec<String> = Vec::new();
+    for path in inputs {
+        let reader_result = build_reader(&opt, path);
+        let mut r = match reader_result {
+            Ok(reader) => reader,
+            Err(e) => {
+                // build_reader fails for files and the clipboard, stdin always opens
+                match path {
+                    Some(path) => eprintln!("{}", open_error_message(path, &e)),
+                    None if opt.clipboard => eprintln!("Failed to read the clipboard ({})", e),
+                    None => eprintln!("Failed to open file. ({})", e),
+                }
+                std::process::exit(1);
+            }
+        };
+
+        // rows above the header are skipped before they can fail to parse
+        let records = r.records().skip(rows_to_skip(&opt)).collect::<Vec<_>>();
+        //.take(row_display_option + 1);
+
+        let mut records = if opt.skip_invalid_rows {
+            let (records, skipped) = keep_valid_records(records);
+            skipped_rows.extend(skipped.into_iter().map(|line| match path {
+                Some(path) if files.len() > 1 => format!("{}:{}", path.display(), line),
+                _ => line.to_string(),
+            }));
+            records
+        } else {
+            records
+                .into_iter()
+                .map(|record| record.expect("valid csv data"))
+                .collect::<Vec<_>>()
+        };
+
+        if opt.no_header && !records.is_empty() {
+            records.insert(0, synthesize_header(&records));
+        }
+
+        match path {
+            Some(path) if files.len() > 1 => {
+                if let Err(e) = append_file_records(&mut rdr, records, path) {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+            _ => rdr = records,
+        }
+    }
 
     if debug_mode {
         println!("{:?}", "StringRecord");
@@ -676,31 +1303,247 @@ fn main() {
     }
 
     if rdr.is_empty() {
-        panic!("🤖 Looks like the file exists, but is empty. No data to read. 🤖")
+        eprintln!("🤖 Looks like the file exists, but is empty. No data to read. 🤖");
+        return;
+    };
+
+    // numbers are read with a decimal point from here on
+    let rdr = if opt.decimal_comma {
+        to_decimal_points(rdr)
+    } else {
+        rdr
+    };
+
+    let rdr = match mutate_records(rdr, &opt.mutate, &missing_options) {
+        Ok(rdr) => rdr,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let rdr = if !opt.select.is_empty() {
+        select_columns(rdr, &opt.select)
+    } else if !opt.drop.is_empty() {
+        drop_columns(rdr, &opt.drop)
+    } else if let Some(pattern) = &opt.select_regex {
+        select_matching_columns(rdr, pattern)
+    } else {
+        Ok(rdr)
+    };
+    let rdr = match rdr {
+        Ok(rdr) => rdr,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let rdr = if opt.only_na_rows || opt.drop_na_rows {
+        filter_na_rows(rdr, opt.na_how, opt.only_na_rows, &missing_options)
+    } else {
+        rdr
+    };
+    let rdr = if opt.drop_empty || opt.drop_constant {
+        match drop_uninformative_columns(rdr, opt.drop_constant, &missing_options) {
+            Ok(rdr) => rdr,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        rdr
+    };
+    let (rdr, duplicates) = if opt.unique || !opt.unique_by.is_empty() {
+        match unique_records(rdr, &opt.unique_by) {
+            Ok((rdr, duplicates)) => (rdr, Some(duplicates)),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        (rdr, None)
+    };
+    let rdr = match &opt.count {
+        Some(column) => match count_values(rdr, column, &missing_options) {
+            Ok(rdr) => rdr,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        None => rdr,
+    };
+    // the sorted column and whether it is descending, marked in the header
+    let (rdr, sorted) = match &opt.sort {
+        Some(key) => match sort_records(rdr, key, &missing_options) {
+            Ok((rdr, idx)) => (rdr, Some((idx, key.descending))),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        None => (rdr, None),
+    };
+    // the records and columns of the file, reported for a transposed table
+    let file_dims: (usize, usize) = (rdr.len() - 1, rdr[0].len());
+    let rdr = if opt.transpose {
+        // a view of the table only turns the records it shows into columns
+        let is_view = output_format != output::OutputFormat::Json && !opt.glimpse && !opt.na_report;
+        let is_every_record = is_force_all_rows || (is_all_rows && !is_row_display_defined);
+        let shown = match is_view && !is_every_record {
+            true => rows_to_show(rdr.len(), Some(*row_display_option)),
+            false => rdr.len(),
+        };
+        transpose(&rdr[..shown])
+    } else {
+        rdr
     };
     let cols: usize = rdr[0].len();
     let rows_in_file: usize = rdr.len();
-    let rows: usize = if extend_width_length_option {
-        // if extend_width_length_option print rows in file unless -n is set (issue #140)
-        if is_row_display_defined {
-            rdr.len().min(row_display_option + 1)
+
+    // json is a conversion of the data rather than a view of it, every row is written
+    if output_format == output::OutputFormat::Json {
+        let columns: Vec<Vec<&str>> = (0..cols)
+            .map(|col| {
+                rdr.iter()
+                    .map(|row| row.get(col).unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+        write_output(
+            output::to_json(&columns, &missing_options).as_bytes(),
+            opt.output.as_deref(),
+        );
+        return;
+    }
+
+    if opt.glimpse {
+        let columns: Vec<Vec<&str>> = (0..cols)
+            .map(|col| {
+                rdr.iter()
+                    .map(|row| row.get(col).unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+        let is_color = is_tty || is_force_color;
+        let mut text = String::new();
+        if leading_newline {
+            text.push('\n');
+        }
+        let dims = format!("Rows: {}\nColumns: {}", rows_in_file - 1, cols);
+        if is_color {
+            text.push_str(&format!("{}\n", dims.color(meta_color)));
         } else {
-            rdr.len().min(rows_in_file + 1)
+            text.push_str(&format!("{}\n", dims));
         }
-    } else {
-        rdr.len().min(row_display_option + 1)
+        for line in glimpse::glimpse(&columns, term_tuple.0 as usize, &missing_options) {
+            if is_color {
+                text.push_str(&format!(
+                    "{} {} {} {}\n",
+                    "$".color(meta_color),
+                    line.name.color(header_color).bold(),
+                    line.dtype.color(meta_color),
+                    line.values.color(std_color)
+                ));
+            } else {
+                text.push_str(&format!("$ {} {} {}\n", line.name, line.dtype, line.values));
+            }
+        }
+        write_output(text.as_bytes(), opt.output.as_deref());
+        return;
+    }
+
+    if opt.na_report {
+        let is_color = is_tty || is_force_color;
+        let data_rows = rows_in_file - 1;
+        let counts = na_counts(&rdr, &missing_options);
+        let name_width = rdr[0].iter().map(|name| name.width()).max().unwrap_or(0);
+        let count_width = counts
+            .iter()
+            .map(|count| count.to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max(2);
+        let mut text = String::new();
+        if leading_newline {
+            text.push('\n');
+        }
+        let dims = format!("Rows: {}\nColumns: {}", data_rows, cols);
+        if is_color {
+            text.push_str(&format!("{}\n", dims.color(meta_color)));
+        } else {
+            text.push_str(&format!("{}\n", dims));
+        }
+        for (name, &count) in rdr[0].iter().zip(&counts) {
+            let percent = if data_rows == 0 {
+                0.0
+            } else {
+                100.0 * count as f64 / data_rows as f64
+            };
+            let name = format!("{}{}", name, " ".repeat(name_width - name.width()));
+            let count = format!("{:>w$} {:>5.1}%", count, percent, w = count_width);
+            if is_color {
+                text.push_str(&format!(
+                    "{} {}\n",
+                    name.color(header_color).bold(),
+                    count.color(na_color)
+                ));
+            } else {
+                text.push_str(&format!("{} {}\n", name, count));
+            }
+        }
+        write_output(text.as_bytes(), opt.output.as_deref());
+        return;
+    }
+
+    // --offset, --tail and --range leave out the first data rows, row numbers
+    // still count from the top of the file
+    let (offset, window) = row_window(rows_in_file - 1, opt.offset, opt.tail, opt.range);
+    let mut rdr = rdr;
+    rdr.drain(1..offset + 1);
+    if let Err(e) = to_percents(&mut rdr, &opt.percent) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    if opt.iso_dates {
+        to_iso_dates(&mut rdr, &missing_options);
+    }
+    if opt.whole_seconds || opt.tz.is_some() {
+        to_date_times(&mut rdr, opt.tz);
+    }
+    if let Err(e) = from_epochs(
+        &mut rdr,
+        &opt.epoch_cols,
+        opt.epoch,
+        opt.tz,
+        &missing_options,
+    ) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+    let records: usize = rdr.len();
+
+    // with --all-rows print rows in file unless -n is set (issue #140)
+    let rows: usize = match window {
+        Some(n) => rows_to_show(records, Some(n)),
+        // -n already picked the records a transposed table shows, its rows are the columns
+        None if opt.transpose => rows_to_show(records, None),
+        None if is_all_rows && !is_row_display_defined => rows_to_show(records, None),
+        None => rows_to_show(records, Some(*row_display_option)),
     };
 
     //let rows_remaining: usize = rows_in_file - rows;
     let rows_remaining: usize = match is_force_all_rows {
         true => 0,
-        false => rows_in_file - rows,
+        false => records - rows,
     };
 
     let rows = match is_force_all_rows {
-        true => rows_in_file,
+        true => records,
         false => rows,
     };
+    let gutter_width: usize = fit_gutter_width(gutter_width, offset + rows - 1);
 
     let ellipsis = '\u{2026}'.to_string();
     let row_remaining_text: String = format!("{} with {} more rows", ellipsis, rows_remaining);
@@ -725,17 +1568,71 @@ fn main() {
         // make datatypes vector
         let mut vec_datatypes = Vec::with_capacity(cols);
         for column in &v {
-            vec_datatypes.push(datatype::get_col_data_type(column))
+            vec_datatypes.push(datatype::get_col_data_type(column, &missing_options))
         }
         println!("{:?}", "vec_datatypes");
         println!("{:?}", vec_datatypes);
     }
 
     // vector of formatted values
-    let vf: Vec<Vec<String>> = v
+    let format_options = datatype::FormatOptions {
+        lower_column_width,
+        upper_column_width,
+        sigfig,
+        integer_mode: opt.integer_sigfig,
+        radix: opt.radix,
+        gutter_width,
+        leading_newline,
+        decimal_comma: opt.show_decimal_comma,
+        ..missing_options
+    };
+    // the row number gutter and the two spaces after it
+    let gutter_sep: &str = if format_options.gutter_width > 0 {
+        "  "
+    } else {
+        ""
+    };
+    // line each displayed record starts on in the source, shown with --source-lines
+    let source_lines: Vec<u64> = rdr
+        .iter()
+        .take(rows)
+        .map(|record| record.position().map_or(0, |pos| pos.line()))
+        .collect();
+    let source_line_width: usize = source_lines.iter().max().unwrap_or(&0).to_string().len();
+    let source_gutter_width: usize = if opt.source_lines {
+        source_line_width + 2
+    } else {
+        0
+    };
+    let total_gutter_width = gutter_width + source_gutter_width;
+
+    // a table that fits the terminal only formats the columns that could be
+    // shown, found from how narrow each column can be
+    let is_fitted =
+        output_format == output::OutputFormat::Table && !is_all_columns && !opt.fit_report;
+    let cols_to_format = if is_fitted {
+        let min_widths: Vec<usize> = v
+            .iter()
+            .map(|col| datatype::min_column_width(col[0], &format_options))
+            .collect();
+        let fitting = layout::fit(
+            &min_widths,
+            term_tuple.0 as usize,
+            total_gutter_width + gutter_sep.len(),
+        );
+        fitting.widths.len().min(opt.max_cols.unwrap_or(cols))
+    } else {
+        cols
+    };
+    let mut vf: Vec<Vec<String>> = v[..cols_to_format]
         .iter()
-        .map(|col| datatype::format_strings(col, lower_column_width, upper_column_width, sigfig))
+        .map(|col| datatype::format_strings(col, &format_options))
         .collect();
+    // a transposed table has the columns as rows, there is no header to mark
+    if let Some((idx, descending)) = sorted.filter(|&(idx, _)| !opt.transpose && idx < vf.len()) {
+        let arrow = if descending { "\u{25bc}" } else { "\u{25b2}" };
+        datatype::annotate_header(&mut vf[idx], v[idx][0], arrow, &format_options);
+    }
 
     if debug_mode {
         println!("{:?}", "Transposed Vector of Elements");
@@ -744,402 +1641,339 @@ fn main() {
         println!("{:?}", vf);
     }
 
-    println!();
     let mut vp: Vec<Vec<String>> = Vec::new();
     for r in 0..rows {
         let row = vf.iter().map(|col| col[r].to_string()).collect();
         vp.push(row);
     }
+    // what each shown cell holds, which picks its color when it is printed
+    let roles: Vec<Vec<datatype::CellRole>> = v
+        .iter()
+        .zip(&vf)
+        .map(|(raw, shown)| datatype::cell_roles(&raw[..rows], &shown[..rows], &format_options))
+        .collect();
+    let mut vp_roles: Vec<Vec<datatype::CellRole>> = Vec::new();
+    for r in 0..rows {
+        let row = roles.iter().map(|col| col[r]).collect();
+        vp_roles.push(row);
+    }
 
-    let num_cols_to_print = if extend_width_length_option {
+    let col_widths: Vec<usize> = vp[0].iter().map(|name| name.width()).collect();
+    let num_cols_to_print = if is_all_columns {
         cols
     } else {
-        get_num_cols_to_print(cols, vp.clone(), term_tuple)
+        layout::fit(
+            &col_widths,
+            term_tuple.0 as usize,
+            total_gutter_width + gutter_sep.len(),
+        )
+        .widths
+        .len()
     };
+    let num_cols_to_print = num_cols_to_print.min(opt.max_cols.unwrap_or(cols));
+    if opt.fit_report {
+        let report = fit_report(
+            &v,
+            &vp,
+            num_cols_to_print,
+            term_tuple.0 as usize,
+            total_gutter_width + gutter_sep.len(),
+        );
+        eprintln!("{}", report);
+    }
 
-    // color
-    let meta_text: &str = "tv dim:";
-    let div: &str = "x";
-    let _ = match stdout!("{: >6}  ", "") {
-        Ok(_) => Ok(()),
-        Err(e) => match e.kind() {
-            std::io::ErrorKind::BrokenPipe => Ok(()),
-            _ => Err(e),
-        },
-    };
-    if !is_no_dimensions {
-        if is_tty || is_force_color {
-            let _ = match stdoutln!(
-                "{} {} {} {}",
-                meta_text.truecolor(meta_color[0], meta_color[1], meta_color[2]), // tv dim:
-                (rows_in_file - 1).truecolor(meta_color[0], meta_color[1], meta_color[2]), // rows
-                div.truecolor(meta_color[0], meta_color[1], meta_color[2]),       // x
-                (cols).truecolor(meta_color[0], meta_color[1], meta_color[2]),    // cols
-            ) {
-                Ok(_) => Ok(()),
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::BrokenPipe => Ok(()),
-                    _ => Err(e),
-                },
-            };
+    // csv writes what the table would show, html, latex and markdown have no
+    // terminal width to fit and show every column up to --max-cols
+    if output_format != output::OutputFormat::Table {
+        let shown_cols = match output_format {
+            output::OutputFormat::Csv | output::OutputFormat::Tsv => num_cols_to_print,
+            _ => cols.min(opt.max_cols.unwrap_or(cols)),
+        };
+        let vp: Vec<Vec<String>> = vp
+            .into_iter()
+            .map(|row| row.into_iter().take(shown_cols).collect())
+            .collect();
+        let vp_roles: Vec<Vec<datatype::CellRole>> = vp_roles
+            .into_iter()
+            .map(|row| row.into_iter().take(shown_cols).collect())
+            .collect();
+        let (dim_rows, dim_cols, shown_rows, shown_cols) = if opt.transpose {
+            (
+                file_dims.0,
+                file_dims.1,
+                shown_cols.saturating_sub(1),
+                rows - 1,
+            )
         } else {
-            let _ = match stdoutln!("{} {} {} {}", meta_text, rows_in_file - 1, div, cols) {
-                Ok(_) => Ok(()),
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::BrokenPipe => Ok(()),
-                    _ => Err(e),
-                },
-            };
-        }
-    } else if is_tty || is_force_color {
-        let _ = match stdoutln!(
-            "{} {} {} {}",
-            "", // tv dim:
-            "", // rows
-            "", // x
-            "", // cols
-        ) {
-            Ok(_) => Ok(()),
-            Err(e) => match e.kind() {
-                std::io::ErrorKind::BrokenPipe => Ok(()),
-                _ => Err(e),
-            },
+            (rows_in_file - 1, cols, rows - 1, shown_cols)
         };
-    } else {
-        let _ = match stdoutln!("{} {} {} {}", meta_text, rows_in_file - 1, div, cols) {
-            Ok(_) => Ok(()),
-            Err(e) => match e.kind() {
-                std::io::ErrorKind::BrokenPipe => Ok(()),
-                _ => Err(e),
-            },
+        let shown_text: String = if shown_rows < dim_rows || shown_cols < dim_cols {
+            format!(" (showing {} x {})", shown_rows, shown_cols)
+        } else {
+            "".to_string()
         };
-    }
-
-    // title
-    if !datatype::is_na(&title_option.clone()) {
-        let _ = match stdout!("{: >6}  ", "") {
-            Ok(_) => Ok(()),
-            Err(e) => match e.kind() {
-                std::io::ErrorKind::BrokenPipe => Ok(()),
-                _ => Err(e),
-            },
+        let notes = output::Notes {
+            title: Some(title_option.as_str()).filter(|t| !is_unset(t)),
+            dims: Some(format!("tv dim: {} x {}{}", dim_rows, dim_cols, shown_text))
+                .filter(|_| !is_no_dimensions),
+            more_rows: Some(format!("{} with {} more rows", ellipsis, rows_remaining))
+                .filter(|_| rows_remaining > 0),
+            footer: Some(footer_option.as_str()).filter(|f| !is_unset(f)),
         };
-        if is_tty || is_force_color {
-            let _ = match stdoutln!(
-                "{}",
-                title_option
-                    .truecolor(meta_color[0], meta_color[1], meta_color[2])
-                    .underline()
-                    .bold()
-            ) {
-                Ok(_) => Ok(()),
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::BrokenPipe => Ok(()),
-                    _ => Err(e),
-                },
-            };
-        } else {
-            let _ = match stdoutln!("{}", title_option) {
-                Ok(_) => Ok(()),
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::BrokenPipe => Ok(()),
-                    _ => Err(e),
-                },
-            };
-        }
+        let row_numbers = Some(offset).filter(|_| !is_no_row_numbering);
+        let text = match output_format {
+            output::OutputFormat::Csv | output::OutputFormat::Tsv => {
+                let default_delimiter = match output_format {
+                    output::OutputFormat::Tsv => b'\t',
+                    _ => b',',
+                };
+                let style = output::CsvStyle {
+                    delimiter: opt.output_delimiter.unwrap_or(default_delimiter),
+                    quote: opt.quote,
+                };
+                match output::to_csv(&v[..num_cols_to_print], style) {
+                    Ok(csv) => csv,
+                    Err(e) => {
+                        eprintln!("Failed to write csv ({})", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            output::OutputFormat::Latex => {
+                output::to_latex(&vp, &vp_roles, row_numbers, &notes).into_bytes()
+            }
+            output::OutputFormat::Markdown => {
+                output::to_markdown(&vp, &vp_roles, row_numbers, &notes).into_bytes()
+            }
+            _ => output::to_html(&vp, &vp_roles, row_numbers, &palette, &notes).into_bytes(),
+        };
+        write_output(&text, opt.output.as_deref());
+        return;
     }
 
-    // header
-    let _ = match stdout!("{: >6}  ", "") {
-        Ok(_) => Ok(()),
-        Err(e) => match e.kind() {
-            std::io::ErrorKind::BrokenPipe => Ok(()),
-            _ => Err(e),
+    let mut out: Box<dyn Write> = match &opt.output {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(io::BufWriter::new(file)),
+            Err(e) => {
+                eprintln!("Failed to write the output ({}: {})", path.display(), e);
+                std::process::exit(1);
+            }
         },
+        None => Box::new(io::stdout().lock()),
     };
-    //for col in 0..cols {
-    for col in 0..num_cols_to_print {
-        let text = vp[0].get(col).unwrap().to_string();
-        if is_tty || is_force_color {
-            let _ = match stdout!(
-                "{}",
-                text.truecolor(header_color[0], header_color[1], header_color[2])
-                    .bold()
-            ) {
-                Ok(_) => Ok(()),
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::BrokenPipe => Ok(()),
-                    _ => Err(e),
-                },
-            };
-        } else {
-            let _ = match stdout!("{}", text) {
-                Ok(_) => Ok(()),
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::BrokenPipe => Ok(()),
-                    _ => Err(e),
-                },
-            };
-        }
+    let renderer = render::Renderer {
+        colors: Some(render::Colors {
+            meta: meta_color,
+            header: header_color,
+            std: std_color,
+            na: na_color,
+            neg_num: neg_num_color,
+            highlight: highlight_color,
+        })
+        .filter(|_| is_tty || is_force_color),
+        highlight: opt.highlight.as_ref(),
+    };
+    // the gutter left of the lines that are not rows
+    let gutter = || {
+        span(
+            format!("{: >w$}{}", "", gutter_sep, w = total_gutter_width),
+            CellStyle::Plain,
+        )
+    };
+    let mut lines: Vec<render::Line> = Vec::new();
+    if format_options.leading_newline {
+        lines.push(vec![]);
     }
-    //println!();
-    // datatypes
-    //print!("{: >6}  ", "");
-    //for col in 0..cols{
-    //    let add_space = vec_datatypes[col].len() - col_largest_width[col];
-    //    let mut owned_string: String = vec_datatypes[col].to_string();
-    //    let borrowed_string: &str = &" ".repeat(add_space);
-    //    owned_string.push_str(borrowed_string);
-    //    print!("{}",owned_string.truecolor(143, 188, 187).bold());
-    //}
-    let _ = match stdoutln!() {
-        Ok(_) => Ok(()),
-        Err(e) => match e.kind() {
-            std::io::ErrorKind::BrokenPipe => Ok(()),
-            _ => Err(e),
-        },
+    // text pasted in slack keeps its columns in a code block
+    let is_code_block = opt.preset == Some(Preset::Slack);
+    if is_code_block {
+        lines.push(vec![span("```", CellStyle::Plain)]);
+    }
+
+    // dimensions
+    let meta_text: &str = "tv dim:";
+    let div: &str = "x";
+    // only mention what is shown when rows or columns are cut off
+    // a transposed table reports the records and columns of the file, its columns
+    // past the first are the records shown
+    let (dim_rows, dim_cols, shown_rows, shown_cols) = if opt.transpose {
+        (
+            file_dims.0,
+            file_dims.1,
+            num_cols_to_print.saturating_sub(1),
+            rows - 1,
+        )
+    } else {
+        (rows_in_file - 1, cols, rows - 1, num_cols_to_print)
     };
-    // main body rows after the column names
-    vp.iter()
-        .enumerate()
-        .take(rows)
-        .skip(1)
-        .for_each(|(i, row)| {
-            if is_tty || is_force_color {
-                if is_no_row_numbering {
-                    let _ = match stdout!(
-                        "{: >6}  ",
-                        "".truecolor(meta_color[0], meta_color[1], meta_color[2]) // this prints the row number
-                    ) {
-                        Ok(_) => Ok(()),
-                        Err(e) => match e.kind() {
-                            std::io::ErrorKind::BrokenPipe => Ok(()),
-                            _ => Err(e),
-                        },
-                    };
-                } else {
-                    let _ = match stdout!(
-                        "{: >6}  ",
-                        i.truecolor(meta_color[0], meta_color[1], meta_color[2]) // this prints the row number
-                    ) {
-                        Ok(_) => Ok(()),
-                        Err(e) => match e.kind() {
-                            std::io::ErrorKind::BrokenPipe => Ok(()),
-                            _ => Err(e),
-                        },
-                    };
-                }
-            } else if is_no_row_numbering {
-                let _ = match stdout!("{: >6}  ",
-                ""                                                           // this prints the row number
-            ) {
-                    Ok(_) => Ok(()),
-                    Err(e) => match e.kind() {
-                        std::io::ErrorKind::BrokenPipe => Ok(()),
-                        _ => Err(e),
-                    },
-                };
-            } else {
-                let _ = match stdout!("{: >6}  ",
-                ""                                                           // this prints the row number
-            ) {
-                    Ok(_) => Ok(()),
-                    Err(e) => match e.kind() {
-                        std::io::ErrorKind::BrokenPipe => Ok(()),
-                        _ => Err(e),
-                    },
-                };
-            }
-            row.iter().take(num_cols_to_print).for_each(|col| {
-                if is_tty || is_force_color {
-                    let _ = match stdout!(
-                        "{}",
-                        if datatype::is_na_string_padded(col) {
-                            col.truecolor(na_color[0], na_color[1], na_color[2])
-                        } else if datatype::is_number(col) && datatype::is_negative_number(col) {
-                            col.truecolor(neg_num_color[0], neg_num_color[1], neg_num_color[2])
-                        } else {
-                            col.truecolor(std_color[0], std_color[1], std_color[2])
-                        }
-                    ) {
-                        Ok(_) => Ok(()),
-                        Err(e) => match e.kind() {
-                            std::io::ErrorKind::BrokenPipe => Ok(()),
-                            _ => Err(e),
-                        },
-                    };
-                } else {
-                    let _ = match stdout!("{}", col) {
-                        Ok(_) => Ok(()),
-                        Err(e) => match e.kind() {
-                            std::io::ErrorKind::BrokenPipe => Ok(()),
-                            _ => Err(e),
-                        },
-                    };
-                }
-            });
-            let _ = match stdoutln!() {
-                Ok(_) => Ok(()),
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::BrokenPipe => Ok(()),
-                    _ => Err(e),
-                },
-            };
-        });
+    let shown_text: String = if shown_rows < dim_rows || shown_cols < dim_cols {
+        format!(" (showing {} {} {})", shown_rows, div, shown_cols)
+    } else {
+        "".to_string()
+    };
+    if is_diff_friendly {
+        // no dimensions line, the row count changes between versions of a file
+    } else if !is_no_dimensions || renderer.colors.is_none() {
+        lines.push(vec![
+            gutter(),
+            span(meta_text, CellStyle::Meta),
+            span(" ", CellStyle::Plain),
+            span(dim_rows.to_string(), CellStyle::Meta),
+            span(" ", CellStyle::Plain),
+            span(div, CellStyle::Meta),
+            span(" ", CellStyle::Plain),
+            span(dim_cols.to_string(), CellStyle::Meta),
+            span(shown_text, CellStyle::Meta),
+        ]);
+    } else {
+        // the blank tv dim, rows, x and cols
+        lines.push(vec![gutter(), span("   ", CellStyle::Plain)]);
+    }
 
-    // additional row info
-    if rows_remaining > 0 || (cols - num_cols_to_print) > 0 {
-        let _ = match stdout!("{: >6}  ", "") {
-            Ok(_) => Ok(()),
-            Err(e) => match e.kind() {
-                std::io::ErrorKind::BrokenPipe => Ok(()),
-                _ => Err(e),
-            },
+    // title
+    if !is_unset(title_option) {
+        lines.push(vec![gutter(), span(title_option, CellStyle::Title)]);
+    }
+
+    // header
+    let mut header = vec![gutter()];
+    for (col, column) in v.iter().enumerate().take(num_cols_to_print) {
+        let style = match opt.na_threshold {
+            Some(threshold) if mostly_missing(column, threshold, &format_options) => {
+                CellStyle::NaHeader
+            }
+            _ => CellStyle::Header,
         };
-        if is_tty || is_force_color {
-            let _ = match stdout!(
-                "{}",
-                row_remaining_text.truecolor(meta_color[0], meta_color[1], meta_color[2])
-            ) {
-                Ok(_) => Ok(()),
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::BrokenPipe => Ok(()),
-                    _ => Err(e),
-                },
-            };
+        header.push(span(&vp[0][col], style));
+    }
+    lines.push(header);
+
+    // main body rows after the column names
+    for (i, row) in vp.into_iter().enumerate().take(rows).skip(1) {
+        // no row numbers when piped
+        let row_number = if is_no_row_numbering || renderer.colors.is_none() {
+            String::new()
         } else {
-            let _ = match stdout!("{}", row_remaining_text) {
-                Ok(_) => Ok(()),
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::BrokenPipe => Ok(()),
-                    _ => Err(e),
-                },
-            };
+            (offset + i).to_string()
+        };
+        let mut line = vec![
+            span(
+                format!("{: >w$}", row_number, w = gutter_width),
+                CellStyle::Meta,
+            ),
+            span(gutter_sep, CellStyle::Plain),
+        ];
+        if opt.source_lines {
+            line.push(span(
+                format!("{: >w$}", source_lines[i], w = source_line_width),
+                CellStyle::Meta,
+            ));
+            line.push(span("  ", CellStyle::Plain));
         }
-        let extra_cols_to_mention = num_cols_to_print;
-        let remainder_cols = cols - extra_cols_to_mention;
-        if extra_cols_to_mention < cols {
-            let meta_text_and = "and";
-            let meta_text_var = "more variables";
-            let meta_text_comma = ",";
-            let meta_text_colon = ":";
-            if is_tty || is_force_color {
-                let _ = match stdout!(
-                    " {} {} {}{}",
-                    meta_text_and.truecolor(meta_color[0], meta_color[1], meta_color[2]),
-                    remainder_cols.truecolor(meta_color[0], meta_color[1], meta_color[2]),
-                    meta_text_var.truecolor(meta_color[0], meta_color[1], meta_color[2]),
-                    meta_text_colon.truecolor(meta_color[0], meta_color[1], meta_color[2])
-                ) {
-                    Ok(_) => Ok(()),
-                    Err(e) => match e.kind() {
-                        std::io::ErrorKind::BrokenPipe => Ok(()),
-                        _ => Err(e),
-                    },
-                };
-            } else {
-                let _ = match stdout!(
-                    " {} {} {}{}",
-                    meta_text_and,
-                    remainder_cols,
-                    meta_text_var,
-                    meta_text_colon
-                ) {
-                    Ok(_) => Ok(()),
-                    Err(e) => match e.kind() {
-                        std::io::ErrorKind::BrokenPipe => Ok(()),
-                        _ => Err(e),
-                    },
-                };
-            }
-            for col in extra_cols_to_mention..cols {
-                let text = rdr[0].get(col).unwrap();
-                if is_tty || is_force_color {
-                    let _ = match stdout!(
-                        " {}",
-                        text.truecolor(meta_color[0], meta_color[1], meta_color[2])
-                    ) {
-                        Ok(_) => Ok(()),
-                        Err(e) => match e.kind() {
-                            std::io::ErrorKind::BrokenPipe => Ok(()),
-                            _ => Err(e),
-                        },
-                    };
-                } else {
-                    let _ = match stdout!(" {}", text) {
-                        Ok(_) => Ok(()),
-                        Err(e) => match e.kind() {
-                            std::io::ErrorKind::BrokenPipe => Ok(()),
-                            _ => Err(e),
-                        },
-                    };
-                }
+        for (cell, &role) in row.into_iter().zip(&vp_roles[i]).take(num_cols_to_print) {
+            line.push(span(cell, CellStyle::of_cell(role)));
+        }
+        lines.push(line);
+    }
 
+    // additional row info
+    if !is_diff_friendly && (rows_remaining > 0 || (cols - num_cols_to_print) > 0) {
+        let mut line = vec![gutter(), span(&row_remaining_text, CellStyle::Meta)];
+        if num_cols_to_print < cols {
+            line.extend([
+                span(" ", CellStyle::Plain),
+                span("and", CellStyle::Meta),
+                span(" ", CellStyle::Plain),
+                span((cols - num_cols_to_print).to_string(), CellStyle::Meta),
+                span(" ", CellStyle::Plain),
+                span("more variables", CellStyle::Meta),
+                span(":", CellStyle::Meta),
+            ]);
+            for col in num_cols_to_print..cols {
+                line.push(span(" ", CellStyle::Plain));
+                line.push(span(rdr[0].get(col).unwrap(), CellStyle::Meta));
                 // The last column mentioned in foot should not be followed by a comma
                 if col + 1 < cols {
-                    if is_tty || is_force_color {
-                        let _ = match stdout!(
-                            "{}",
-                            meta_text_comma.truecolor(meta_color[0], meta_color[1], meta_color[2])
-                        ) {
-                            Ok(_) => Ok(()),
-                            Err(e) => match e.kind() {
-                                std::io::ErrorKind::BrokenPipe => Ok(()),
-                                _ => Err(e),
-                            },
-                        };
-                    } else {
-                        let _ = match stdout!("{}", meta_text_comma) {
-                            Ok(_) => Ok(()),
-                            Err(e) => match e.kind() {
-                                std::io::ErrorKind::BrokenPipe => Ok(()),
-                                _ => Err(e),
-                            },
-                        };
-                    }
+                    line.push(span(",", CellStyle::Meta));
                 }
-            } // end extra cols mentioned in footer
+            }
         }
+        lines.push(line);
     }
 
-    // footer
-    if !datatype::is_na(&footer_option.clone()) {
-        let _ = match stdout!("{: >6}  ", "") {
-            Ok(_) => Ok(()),
-            Err(e) => match e.kind() {
-                std::io::ErrorKind::BrokenPipe => Ok(()),
-                _ => Err(e),
-            },
+    // precision hint
+    let rounded_cols: Vec<&str> = (0..num_cols_to_print)
+        .filter(|_| opt.precision_hint)
+        .filter(|&col| (1..rows).any(|row| datatype::is_rounded(v[col][row], &vf[col][row])))
+        .map(|col| v[col][0])
+        .collect();
+    if !rounded_cols.is_empty() {
+        let hint_text = format!(
+            "numbers rounded to {} significant figures in: {}",
+            sigfig,
+            rounded_cols.join(", ")
+        );
+        lines.push(vec![gutter(), span(hint_text, CellStyle::Hint)]);
+    }
+
+    // duplicate column names
+    let duplicate_names = duplicate_names(v.iter().map(|col| col[0]));
+    if !duplicate_names.is_empty() && !is_diff_friendly {
+        let names_text = match duplicate_names.len() {
+            1 => format!("(1 duplicate column name: {})", duplicate_names[0]),
+            n => format!(
+                "({} duplicate column names: {})",
+                n,
+                duplicate_names.join(", ")
+            ),
         };
-        if is_tty || is_force_color {
-            let _ = match stdoutln!(
-                "{}",
-                footer_option.truecolor(meta_color[0], meta_color[1], meta_color[2])
-            ) {
-                Ok(_) => Ok(()),
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::BrokenPipe => Ok(()),
-                    _ => Err(e),
-                },
-            };
-        } else {
-            let _ = match stdoutln!("{}", footer_option) {
-                Ok(_) => Ok(()),
-                Err(e) => match e.kind() {
-                    std::io::ErrorKind::BrokenPipe => Ok(()),
-                    _ => Err(e),
-                },
-            };
-        }
+        lines.push(vec![gutter(), span(names_text, CellStyle::Hint)]);
     }
 
-    let _ = match stdoutln!() {
-        Ok(_) => Ok(()),
-        Err(e) => match e.kind() {
-            std::io::ErrorKind::BrokenPipe => Ok(()),
-            _ => Err(e),
-        },
-    };
+    // digest
+    if opt.digest {
+        let digest_text = format!("sha256: {}", digest(&v));
+        lines.push(vec![gutter(), span(digest_text, CellStyle::Meta)]);
+    }
+
+    // duplicates
+    if let Some(duplicates) = duplicates.filter(|_| !is_diff_friendly) {
+        let duplicates_text = match duplicates {
+            1 => "1 duplicate row removed".to_string(),
+            n => format!("{} duplicate rows removed", n),
+        };
+        lines.push(vec![gutter(), span(duplicates_text, CellStyle::Meta)]);
+    }
+
+    // rows skipped by -j
+    if opt.verbose_footer && opt.skip_invalid_rows && !is_diff_friendly {
+        let skipped_text = match skipped_rows.len() {
+            0 => "0 invalid rows skipped".to_string(),
+            1 => format!("1 invalid row skipped, on line {}", skipped_rows[0]),
+            n => format!(
+                "{} invalid rows skipped, on lines {}",
+                n,
+                skipped_rows.join(", ")
+            ),
+        };
+        lines.push(vec![gutter(), span(skipped_text, CellStyle::Meta)]);
+    }
+
+    // footer
+    if !is_diff_friendly && !is_unset(footer_option) {
+        lines.push(vec![gutter(), span(footer_option, CellStyle::Meta)]);
+    }
+
+    let end = if is_code_block { "```" } else { "" };
+    lines.push(vec![span(end, CellStyle::Plain)]);
+
+    match renderer.write(&mut out, &lines).and_then(|_| out.flush()) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("Failed to write the output ({})", e);
+            std::process::exit(1);
+        }
+        _ => {}
+    }
 } // end main
 
 fn get_color_from_config(a: &toml::value::Array) -> [u8; 3] {
@@ -1158,51 +1992,1090 @@ fn get_color_from_config(a: &toml::value::Array) -> [u8; 3] {
     i32_array
 }
 
-// how wide will the print be?
-fn get_num_cols_to_print(cols: usize, vp: Vec<Vec<String>>, term_tuple: (u16, u16)) -> usize {
-    let mut last = 0;
-    let mut j = format!("{: >6}  ", "");
-    for col in 0..cols {
-        let text = vp[0].get(col).unwrap().to_string();
-        j.push_str(&text);
-        let total_width = j.chars().count();
-        let term_width = term_tuple.0 as usize;
-        if total_width > term_width {
-            break;
+// Terminal size, falling back to $COLUMNS/$LINES and then 80x24 when the terminal
+// can not be queried (terminal multiplexers, services, CI).
+fn terminal_size(
+    detected: io::Result<(u16, u16)>,
+    columns: Option<String>,
+    lines: Option<String>,
+) -> (u16, u16) {
+    match detected {
+        Ok((w, h)) if w > 0 && h > 0 => (w, h),
+        _ => {
+            let from_env = |v: Option<String>, default: u16| {
+                v.and_then(|v| v.trim().parse::<u16>().ok())
+                    .filter(|&n| n > 0)
+                    .unwrap_or(default)
+            };
+            (from_env(columns, 80), from_env(lines, 24))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    Default,
+    DiffFriendly,
+}
+
+impl std::str::FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(Style::Default),
+            "diff-friendly" => Ok(Style::DiffFriendly),
+            _ => Err(format!("expected default or diff-friendly, got \"{}\"", s)),
+        }
+    }
+}
+
+// Rows numbered from `start`, counting from 1, to `end`, both included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RowRange {
+    start: usize,
+    end: Option<usize>,
+}
+
+impl std::str::FromStr for RowRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "expected a range of rows like 100:150, 100: or :150, got \"{}\"",
+                s
+            )
+        };
+        let (start, end) = s.split_once(':').ok_or_else(error)?;
+        let start = match start.trim() {
+            "" => 1,
+            start => start.parse().map_err(|_| error())?,
+        };
+        let end = match end.trim() {
+            "" => None,
+            end => Some(end.parse().map_err(|_| error())?),
+        };
+        match end {
+            _ if start == 0 => Err(format!("{}: rows are counted from 1", s)),
+            Some(end) if end < start => Err(format!("{}: the range ends before it starts", s)),
+            _ => Ok(RowRange { start, end }),
+        }
+    }
+}
+
+// The number of data rows to leave out before the first one shown and, for
+// --tail and --range, how many to show in place of -n.
+fn row_window(
+    data_rows: usize,
+    offset: usize,
+    tail: Option<usize>,
+    range: Option<RowRange>,
+) -> (usize, Option<usize>) {
+    match (tail, range) {
+        (Some(n), _) => (data_rows.saturating_sub(n), Some(n)),
+        (None, Some(range)) => (
+            (range.start - 1).min(data_rows),
+            Some(range.end.map_or(data_rows, |end| end - range.start + 1)),
+        ),
+        (None, None) => (offset.min(data_rows), None),
+    }
+}
+
+// Where the output will be pasted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Preset {
+    Slack,
+    Github,
+    Email,
+}
+
+impl std::str::FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "slack" => Ok(Preset::Slack),
+            "github" => Ok(Preset::Github),
+            "email" => Ok(Preset::Email),
+            _ => Err(format!("expected slack, github or email, got \"{}\"", s)),
+        }
+    }
+}
+
+impl Preset {
+    // Sets the options of the preset that are not given, so the options given
+    // on the command line win, even when they are the default.
+    fn apply(self, opt: &mut Cli) {
+        let (upper_column_width, sigfig, output_format) = match self {
+            Preset::Slack => (16, 3, output::OutputFormat::Table),
+            Preset::Github => (30, 4, output::OutputFormat::Markdown),
+            Preset::Email => (24, 3, output::OutputFormat::Html),
+        };
+        opt.upper_column_width.get_or_insert(upper_column_width);
+        opt.sigfig.get_or_insert(sigfig);
+        opt.output_format.get_or_insert(output_format);
+        match self {
+            Preset::Slack => opt.no_leading_newline = true,
+            Preset::Github => opt.all_columns = true,
+            Preset::Email => {}
+        }
+    }
+
+    // The width the output is laid out for, in place of the terminal's.
+    fn width(self) -> Option<u16> {
+        match self {
+            Preset::Slack => Some(80),
+            Preset::Github => None,
+            Preset::Email => Some(100),
+        }
+    }
+}
+
+// SHA256 of the shown rows written as csv, so the same values give the same
+// digest whatever the delimiter, quoting or encoding of the file.
+fn digest(v: &[Vec<&str>]) -> String {
+    use sha2::{Digest, Sha256};
+    let csv = output::to_csv(v, output::CsvStyle::default()).unwrap_or_default();
+    Sha256::digest(&csv)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// How many values of each column are missing, over every row after the header.
+fn na_counts(rdr: &[StringRecord], opts: &datatype::FormatOptions) -> Vec<usize> {
+    let mut counts = vec![0; rdr[0].len()];
+    for record in &rdr[1..] {
+        for (col, count) in counts.iter_mut().enumerate() {
+            if datatype::is_missing(record.get(col).unwrap_or_default(), opts) {
+                *count += 1;
+            }
+        }
+    }
+    counts
+}
+
+// The columns that fit the terminal and the ones dropped for width, with the
+// width each one takes, see --fit-report.
+fn fit_report(
+    v: &[Vec<&str>],
+    vp: &[Vec<String>],
+    num_cols_to_print: usize,
+    term_width: usize,
+    gutter_width: usize,
+) -> serde_json::Value {
+    let columns: Vec<serde_json::Value> = v
+        .iter()
+        .zip(&vp[0])
+        .map(|(col, header)| {
+            serde_json::json!({
+                "name": col[0],
+                "width": header.width(),
+            })
+        })
+        .collect();
+    let (shown, dropped) = columns.split_at(num_cols_to_print);
+    serde_json::json!({
+        "terminal_width": term_width,
+        "gutter_width": gutter_width,
+        "shown": shown,
+        "dropped": dropped,
+    })
+}
+
+// True if more than `threshold` percent of the values of `column`, a header
+// and its values, are missing.
+fn mostly_missing(column: &[&str], threshold: f64, opts: &datatype::FormatOptions) -> bool {
+    let values = &column[1.min(column.len())..];
+    if values.is_empty() {
+        return false;
+    }
+    let missing = values
+        .iter()
+        .filter(|&&v| datatype::is_missing(v, opts))
+        .count();
+    missing as f64 * 100.0 > threshold * values.len() as f64
+}
+
+// The names in `header` that are used more than once, each once, in the order
+// they first repeat.
+fn duplicate_names<'a>(header: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+    for name in header {
+        if !seen.insert(name) && !duplicates.contains(&name) {
+            duplicates.push(name);
+        }
+    }
+    duplicates
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NaHow {
+    Any,
+    All,
+}
+
+impl std::str::FromStr for NaHow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "any" => Ok(NaHow::Any),
+            "all" => Ok(NaHow::All),
+            _ => Err(format!("expected any or all, got \"{}\"", s)),
+        }
+    }
+}
+
+// keep the rows that have missing values (keep_na) or the ones that do not.
+// The header is always kept.
+fn filter_na_rows(
+    rdr: Vec<StringRecord>,
+    how: NaHow,
+    keep_na: bool,
+    opts: &datatype::FormatOptions,
+) -> Vec<StringRecord> {
+    rdr.into_iter()
+        .enumerate()
+        .filter(|(i, record)| {
+            if *i == 0 {
+                return true;
+            }
+            let has_na = match how {
+                NaHow::Any => record.iter().any(|cell| datatype::is_missing(cell, opts)),
+                NaHow::All => record.iter().all(|cell| datatype::is_missing(cell, opts)),
+            };
+            has_na == keep_na
+        })
+        .map(|(_, record)| record)
+        .collect()
+}
+
+// Keep the first of the rows that are the same in `columns`, names or 1-based
+// indices, or in every column when there are none. Also returns how many rows
+// were removed.
+fn unique_records(
+    rdr: Vec<StringRecord>,
+    columns: &[String],
+) -> Result<(Vec<StringRecord>, usize), String> {
+    let indices = if columns.is_empty() {
+        (0..rdr[0].len()).collect()
+    } else {
+        column_indices(&rdr[0], columns, "--unique-by")?
+    };
+    let rows = rdr.len();
+    let mut seen: HashSet<Vec<String>> = HashSet::new();
+    let unique: Vec<StringRecord> = rdr
+        .into_iter()
+        .enumerate()
+        .filter(|(i, record)| {
+            let key = indices
+                .iter()
+                .map(|&idx| record.get(idx).unwrap_or_default().to_string())
+                .collect();
+            *i == 0 || seen.insert(key)
+        })
+        .map(|(_, record)| record)
+        .collect();
+    let duplicates = rows - unique.len();
+    Ok((unique, duplicates))
+}
+
+// How often each value of `column` occurs, most frequent first, as records of
+// the value, its count n and its percentage of the rows. Missing values are
+// counted together.
+fn count_values(
+    rdr: Vec<StringRecord>,
+    column: &str,
+    opts: &datatype::FormatOptions,
+) -> Result<Vec<StringRecord>, String> {
+    let idx = column_indices(&rdr[0], &[column.to_string()], "--count")?[0];
+    // values in the order they are first seen, which breaks ties
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    for record in &rdr[1..] {
+        let value = record.get(idx).unwrap_or_default();
+        let value = if datatype::is_missing(value, opts) {
+            ""
+        } else {
+            value
+        };
+        match positions.get(value) {
+            Some(&position) => counts[position].1 += 1,
+            None => {
+                positions.insert(value, counts.len());
+                counts.push((value, 1));
+            }
+        }
+    }
+    counts.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+    let total = rdr.len() - 1;
+    let mut counted = vec![StringRecord::from(vec![&rdr[0][idx], "n", "percent"])];
+    counted.extend(counts.into_iter().map(|(value, n)| {
+        let percent = format!("{:.2}", 100.0 * n as f64 / total as f64);
+        StringRecord::from(vec![value.to_string(), n.to_string(), percent])
+    }));
+    Ok(counted)
+}
+
+// The records that parsed, in file order, and the lines the others start on,
+// for -j.
+fn keep_valid_records(records: Vec<csv::Result<StringRecord>>) -> (Vec<StringRecord>, Vec<u64>) {
+    let mut valid = Vec::with_capacity(records.len());
+    let mut skipped = Vec::new();
+    for record in records {
+        match record {
+            Ok(record) => valid.push(record),
+            Err(e) => skipped.push(e.position().map_or(0, |pos| pos.line())),
+        }
+    }
+    (valid, skipped)
+}
+
+// Column names X1, X2, ... like readr's col_names = FALSE, for files without
+// a header row.
+fn synthesize_header(records: &[StringRecord]) -> StringRecord {
+    let cols = records.iter().map(|record| record.len()).max().unwrap_or(0);
+    (1..=cols).map(|i| format!("X{}", i)).collect()
+}
+
+// Columns become rows: the first column holds the column names and the
+// records are columns named by their row number.
+fn transpose(records: &[StringRecord]) -> Vec<StringRecord> {
+    let mut header = StringRecord::from(vec!["column"]);
+    for i in 1..records.len() {
+        header.push_field(&i.to_string());
+    }
+    let mut transposed = vec![header];
+    for (col, name) in records[0].iter().enumerate() {
+        let mut row = StringRecord::from(vec![name]);
+        for record in &records[1..] {
+            row.push_field(record.get(col).unwrap_or_default());
+        }
+        transposed.push(row);
+    }
+    transposed
+}
+
+// The records to show, the header included. `-n 0` shows only the header and
+// an -n past the end of the file shows every row.
+fn rows_to_show(records: usize, row_display: Option<usize>) -> usize {
+    match row_display {
+        Some(n) => records.min(n.saturating_add(1)),
+        None => records,
+    }
+}
+
+// The gutter grows to fit the last row number, so millions of rows printed
+// with -f keep the columns aligned. A zero width gutter stays off.
+fn fit_gutter_width(gutter_width: usize, last_row: usize) -> usize {
+    if gutter_width == 0 {
+        0
+    } else {
+        gutter_width.max(last_row.to_string().len())
+    }
+}
+
+// Tells whether the latest release on GitHub is newer than this tv.
+#[cfg(feature = "http")]
+fn check_update() {
+    let current = env!("CARGO_PKG_VERSION");
+    match update::latest_release() {
+        Ok(release) if update::is_newer(&release.tag_name, current) => println!(
+            "tv {} is out, this is {}. Get it from {}",
+            release.tag_name.trim_start_matches('v'),
+            current,
+            update::RELEASES_PAGE
+        ),
+        Ok(_) => println!("tv {} is the latest release", current),
+        Err(e) => {
+            eprintln!("Failed to check for a newer release ({})", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Installs the latest release on GitHub in place of this tv when it is newer.
+#[cfg(feature = "self-update")]
+fn self_update() {
+    let current = env!("CARGO_PKG_VERSION");
+    let release = match update::latest_release() {
+        Ok(release) => release,
+        Err(e) => {
+            eprintln!("Failed to check for a newer release ({})", e);
+            std::process::exit(1);
+        }
+    };
+    if !update::is_newer(&release.tag_name, current) {
+        println!("tv {} is the latest release", current);
+        return;
+    }
+    match update::install(&release) {
+        Ok(()) => println!(
+            "Updated tv from {} to {}",
+            current,
+            release.tag_name.trim_start_matches('v')
+        ),
+        Err(e) => {
+            eprintln!("Failed to update tv ({})", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Shows `text` in $PAGER, or less, when stdout is a terminal and prints it
+// otherwise or when there is no pager.
+fn show_in_pager(text: &str) {
+    if atty::is(atty::Stream::Stdout) {
+        let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+        let mut words = pager.split_whitespace();
+        if let Some(program) = words.next() {
+            let child = std::process::Command::new(program)
+                .args(words)
+                .stdin(std::process::Stdio::piped())
+                .spawn();
+            if let Ok(mut child) = child {
+                if let Some(mut stdin) = child.stdin.take() {
+                    let _ = stdin.write_all(text.as_bytes());
+                }
+                let _ = child.wait();
+                return;
+            }
+        }
+    }
+    let _ = io::stdout().write_all(text.as_bytes());
+}
+
+// Writes a non-table output to the --output file, or to stdout.
+fn write_output(text: &[u8], path: Option<&Path>) {
+    let result = match path {
+        Some(path) => std::fs::write(path, text)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e))),
+        None => {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(text).and_then(|_| stdout.flush())
+        }
+    };
+    match result {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            eprintln!("Failed to write the output ({})", e);
+            std::process::exit(1);
+        }
+        _ => {}
+    }
+}
+
+// The rows above the header: --skip-rows, or the ones above --header-row.
+fn rows_to_skip(opt: &Cli) -> usize {
+    match opt.header_row {
+        Some(row) => row.saturating_sub(1),
+        None => opt.skip_rows,
+    }
+}
+
+fn read_clipboard() -> io::Result<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(io::Error::other)
+}
+
+// What went wrong opening `path`, with a hint on what to do about it.
+fn open_error_message(path: &Path, e: &io::Error) -> String {
+    let name = path.display();
+    if path.is_dir() {
+        return format!(
+            "{} is a directory. Pass a file in it, or a glob like '{}'.",
+            name,
+            path.join("*.csv").display()
+        );
+    }
+    match e.kind() {
+        io::ErrorKind::NotFound => format!(
+            "{}: no such file. Check the spelling and the current directory.",
+            name
+        ),
+        io::ErrorKind::PermissionDenied => format!(
+            "{}: permission denied. Check that you can read the file (ls -l {}).",
+            name, name
+        ),
+        // unsupported formats, the message already says what the file is
+        io::ErrorKind::InvalidData => format!("{}: {}", name, e),
+        _ => format!("Failed to open file: {} ({})", name, e),
+    }
+}
+
+// Split every line of `source` on a delimiter of several bytes and write the
+// fields back as csv. Quotes are kept as they are, blank lines stay blank so
+// that line numbers still match the file, and comment lines are copied as is
+// for the csv reader to skip.
+fn split_lines(source: impl Read, delimiter: &[u8], comment: Option<u8>) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    for line in BufReader::new(source).split(b'\n') {
+        let line = line?;
+        let line = line.strip_suffix(b"\r").unwrap_or(&line);
+        if comment.is_some() && line.first() == comment.as_ref() {
+            out.extend_from_slice(line);
+            out.push(b'\n');
+            continue;
+        }
+        let mut fields = Vec::new();
+        let mut rest = line;
+        while !line.is_empty() {
+            match rest.windows(delimiter.len()).position(|w| w == delimiter) {
+                Some(end) => {
+                    fields.push(&rest[..end]);
+                    rest = &rest[end + delimiter.len()..];
+                }
+                None => {
+                    fields.push(rest);
+                    break;
+                }
+            }
+        }
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                out.push(b',');
+            }
+            write_csv_field(&mut out, field);
+        }
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+fn write_csv_field(out: &mut Vec<u8>, field: &[u8]) {
+    if field
+        .iter()
+        .any(|b| matches!(b, b',' | b'"' | b'\r' | b'\n'))
+    {
+        out.push(b'"');
+        for &b in field {
+            if b == b'"' {
+                out.push(b'"');
+            }
+            out.push(b);
+        }
+        out.push(b'"');
+    } else {
+        out.extend_from_slice(field);
+    }
+}
+
+// Expand glob patterns ourselves, shells on Windows leave `data/*.csv` as is.
+// Paths that exist, URLs and patterns without matches are kept unchanged.
+fn expand_globs(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut expanded = Vec::new();
+    for path in paths {
+        let pattern = path.to_string_lossy();
+        let is_pattern = pattern.contains(['*', '?', '[']);
+        let matches: Vec<PathBuf> = if is_pattern && !path.exists() && !remote::is_url(path) {
+            glob::glob(&pattern)
+                .map(|paths| paths.filter_map(Result::ok).collect())
+                .unwrap_or_default()
+        } else {
+            vec![]
+        };
+        if matches.is_empty() {
+            expanded.push(path.clone());
+        } else {
+            expanded.extend(matches);
+        }
+    }
+    expanded
+}
+
+// Add the records of one of several files to the combined table, with the
+// file name as a leading `file` column. Every file must have the same header.
+fn append_file_records(
+    rdr: &mut Vec<StringRecord>,
+    records: Vec<StringRecord>,
+    path: &Path,
+) -> Result<(), String> {
+    let with_file = |file: &str, record: &StringRecord| {
+        let mut out = StringRecord::new();
+        out.push_field(file);
+        out.extend(record.iter());
+        out.set_position(record.position().cloned());
+        out
+    };
+    let mut records = records.into_iter();
+    let header = match records.next() {
+        Some(header) => header,
+        // an empty file adds no rows
+        None => return Ok(()),
+    };
+    if rdr.is_empty() {
+        rdr.push(with_file("file", &header));
+    } else if rdr[0].iter().skip(1).ne(header.iter()) {
+        return Err(format!(
+            "{}: columns differ from the first file, can not show them as one table",
+            path.display()
+        ));
+    }
+    let file = path.display().to_string();
+    rdr.extend(records.map(|record| with_file(&file, &record)));
+    Ok(())
+}
+
+// Keep the columns of `select`, names or 1-based indices, in that order.
+fn select_columns(rdr: Vec<StringRecord>, select: &[String]) -> Result<Vec<StringRecord>, String> {
+    let indices = column_indices(&rdr[0], select, "--select")?;
+    Ok(keep_columns(&rdr, &indices))
+}
+
+// Keep the columns that are not in `drop`, names or 1-based indices.
+fn drop_columns(rdr: Vec<StringRecord>, drop: &[String]) -> Result<Vec<StringRecord>, String> {
+    let dropped = column_indices(&rdr[0], drop, "--drop")?;
+    let indices: Vec<usize> = (0..rdr[0].len())
+        .filter(|idx| !dropped.contains(idx))
+        .collect();
+    Ok(keep_columns(&rdr, &indices))
+}
+
+// Drop the columns where every value is missing or, with `constant`, where
+// every value is the same, a missing value being one value. A table without
+// rows is kept whole.
+fn drop_uninformative_columns(
+    rdr: Vec<StringRecord>,
+    constant: bool,
+    opts: &datatype::FormatOptions,
+) -> Result<Vec<StringRecord>, String> {
+    if rdr.len() < 2 {
+        return Ok(rdr);
+    }
+    // a value that is not missing
+    fn present<'a>(
+        record: &'a StringRecord,
+        col: usize,
+        opts: &datatype::FormatOptions,
+    ) -> Option<&'a str> {
+        Some(record.get(col).unwrap_or_default()).filter(|value| !datatype::is_missing(value, opts))
+    }
+    let indices: Vec<usize> = (0..rdr[0].len())
+        .filter(|&col| {
+            let first = present(&rdr[1], col, opts);
+            let rest = || rdr[2..].iter().map(|record| present(record, col, opts));
+            let is_empty = first.is_none() && rest().all(|value| value.is_none());
+            let is_constant = constant && rest().all(|value| value == first);
+            !is_empty && !is_constant
+        })
+        .collect();
+    if indices.is_empty() {
+        return Err("Every column is empty or constant, there is nothing to show".to_string());
+    }
+    Ok(keep_columns(&rdr, &indices))
+}
+
+// The index of each column of `columns`, a name or a 1-based index. A name wins
+// over an index so a column named "2" can be picked.
+fn column_indices(
+    header: &StringRecord,
+    columns: &[String],
+    flag: &str,
+) -> Result<Vec<usize>, String> {
+    columns
+        .iter()
+        .map(|column| {
+            let column = column.trim();
+            match header.iter().position(|h| h == column) {
+                Some(idx) => Ok(idx),
+                None => match column.parse::<usize>() {
+                    Ok(i) if i >= 1 && i <= header.len() => Ok(i - 1),
+                    Ok(i) => Err(format!(
+                        "{} {}: there are {} columns, counting from 1",
+                        flag,
+                        i,
+                        header.len()
+                    )),
+                    Err(_) => Err(format!("{}: no column named \"{}\"", flag, column)),
+                },
+            }
+        })
+        .collect()
+}
+
+// A column to sort by, ascending unless it ends in :desc.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SortKey {
+    column: String,
+    descending: bool,
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (column, descending) = match s.rsplit_once(':') {
+            Some((column, "desc")) => (column, true),
+            Some((column, "asc")) => (column, false),
+            _ => (s, false),
+        };
+        if column.trim().is_empty() {
+            return Err(format!(
+                "expected a column like price or price:desc, got \"{}\"",
+                s
+            ));
+        }
+        Ok(SortKey {
+            column: column.to_string(),
+            descending,
+        })
+    }
+}
+
+// Sort the rows by the column of `key`, as numbers when the column holds numbers
+// and as text otherwise. Missing values go last either way. The sort is stable so
+// equal values keep their order in the file. Returns the index of the column.
+fn sort_records(
+    mut rdr: Vec<StringRecord>,
+    key: &SortKey,
+    opts: &datatype::FormatOptions,
+) -> Result<(Vec<StringRecord>, usize), String> {
+    let idx = column_indices(&rdr[0], std::slice::from_ref(&key.column), "--sort")?[0];
+    let values: Vec<&str> = rdr[1..]
+        .iter()
+        .map(|record| record.get(idx).unwrap_or_default())
+        .collect();
+    let numeric = matches!(
+        output::column_type(&values, opts),
+        datatype::ValueType::Integer | datatype::ValueType::Double
+    );
+    let number = |text: &str| {
+        let text = text.trim();
+        text.parse::<f64>()
+            .ok()
+            .or_else(|| datatype::parse_radix_integer(text).map(|n| n as f64))
+    };
+
+    rdr[1..].sort_by(|a, b| {
+        let a = a.get(idx).unwrap_or_default();
+        let b = b.get(idx).unwrap_or_default();
+        let (a_missing, b_missing) = (datatype::is_missing(a, opts), datatype::is_missing(b, opts));
+        let order = match (a_missing, b_missing) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Greater,
+            (false, true) => return Ordering::Less,
+            (false, false) if numeric => match (number(a), number(b)) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                // text in a number column goes after the numbers
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            },
+            (false, false) => a.trim().cmp(b.trim()),
+        };
+        if key.descending {
+            order.reverse()
+        } else {
+            order
+        }
+    });
+    Ok((rdr, idx))
+}
+
+// Keep the columns whose names match `pattern`, in file order.
+fn select_matching_columns(
+    rdr: Vec<StringRecord>,
+    pattern: &regex::Regex,
+) -> Result<Vec<StringRecord>, String> {
+    let indices: Vec<usize> = (0..rdr[0].len())
+        .filter(|&idx| pattern.is_match(&rdr[0][idx]))
+        .collect();
+    if indices.is_empty() {
+        return Err(format!(
+            "--select-regex: no column name matches \"{}\"",
+            pattern
+        ));
+    }
+    Ok(keep_columns(&rdr, &indices))
+}
+
+fn keep_columns(rdr: &[StringRecord], indices: &[usize]) -> Vec<StringRecord> {
+    rdr.iter()
+        .map(|record| {
+            let mut kept: StringRecord = indices
+                .iter()
+                .map(|&idx| record.get(idx).unwrap_or_default())
+                .collect();
+            kept.set_position(record.position().cloned());
+            kept
+        })
+        .collect()
+}
+
+// Write the proportions in `columns` of `rdr` as percentages.
+fn to_percents(rdr: &mut [StringRecord], columns: &[String]) -> Result<(), String> {
+    if columns.is_empty() {
+        return Ok(());
+    }
+    let indices = column_indices(&rdr[0], columns, "--percent")?;
+    for record in rdr.iter_mut().skip(1) {
+        let mut converted: StringRecord = record
+            .iter()
+            .enumerate()
+            .map(|(i, field)| match indices.contains(&i) {
+                true => datatype::to_percent(field).unwrap_or_else(|| field.to_string()),
+                false => field.to_string(),
+            })
+            .collect();
+        converted.set_position(record.position().cloned());
+        *record = converted;
+    }
+    Ok(())
+}
+
+// Write the dates of `rdr` as YYYY-MM-DD, in the columns where every value that
+// is not missing under `opts` is a date. A column of numbers that happen to read
+// as 20240131 and the like is left alone when one of them does not.
+fn to_iso_dates(rdr: &mut [StringRecord], opts: &datatype::FormatOptions) {
+    let cols = rdr.first().map_or(0, |header| header.len());
+    let date_cols: Vec<bool> = (0..cols)
+        .map(|col| {
+            let mut values = rdr[1..]
+                .iter()
+                .filter_map(|record| record.get(col))
+                .filter(|field| !field.is_empty() && !datatype::is_missing(field, opts))
+                .peekable();
+            values.peek().is_some() && values.all(|field| datatype::to_iso_date(field).is_some())
+        })
+        .collect();
+    if !date_cols.contains(&true) {
+        return;
+    }
+    for record in rdr.iter_mut().skip(1) {
+        let mut converted: StringRecord = record
+            .iter()
+            .enumerate()
+            .map(|(i, field)| match date_cols.get(i) == Some(&true) {
+                true => datatype::to_iso_date(field).unwrap_or_else(|| field.to_string()),
+                false => field.to_string(),
+            })
+            .collect();
+        converted.set_position(record.position().cloned());
+        *record = converted;
+    }
+}
+
+// Write the date-times of `rdr` to the second, in `tz` when given.
+fn to_date_times(rdr: &mut [StringRecord], tz: Option<datatype::TimeZone>) {
+    for record in rdr.iter_mut().skip(1) {
+        if !record.iter().any(datatype::is_date_time) {
+            continue;
+        }
+        let mut converted: StringRecord = record
+            .iter()
+            .map(|field| datatype::to_date_time(field, tz).unwrap_or_else(|| field.to_string()))
+            .collect();
+        converted.set_position(record.position().cloned());
+        *record = converted;
+    }
+}
+
+// Write the Unix timestamps in `columns` of `rdr` as date-times, in `tz` when
+// given. With `detect` also those of the columns that look like timestamps.
+fn from_epochs(
+    rdr: &mut [StringRecord],
+    columns: &[String],
+    detect: bool,
+    tz: Option<datatype::TimeZone>,
+    opts: &datatype::FormatOptions,
+) -> Result<(), String> {
+    let mut indices = column_indices(&rdr[0], columns, "--epoch-cols")?;
+    if detect {
+        for (col, name) in rdr[0].iter().enumerate() {
+            let column: Vec<&str> = rdr[1..].iter().filter_map(|r| r.get(col)).collect();
+            if datatype::is_epoch_column(name, &column, opts) {
+                indices.push(col);
+            }
+        }
+    }
+    if indices.is_empty() {
+        return Ok(());
+    }
+    for record in rdr.iter_mut().skip(1) {
+        let mut converted: StringRecord = record
+            .iter()
+            .enumerate()
+            .map(|(i, field)| match indices.contains(&i) {
+                true => datatype::from_epoch(field, tz).unwrap_or_else(|| field.to_string()),
+                false => field.to_string(),
+            })
+            .collect();
+        converted.set_position(record.position().cloned());
+        *record = converted;
+    }
+    Ok(())
+}
+
+// True if a title or footer was not given, which leaves it "NA". This sentinel
+// is always checked against the Standard NA set, whatever --na-values is.
+fn is_unset(text: &str) -> bool {
+    datatype::NaValues::Standard.matches(text)
+}
+
+// `rdr` with the numbers written with a decimal comma written with a decimal point.
+fn to_decimal_points(mut rdr: Vec<StringRecord>) -> Vec<StringRecord> {
+    for record in rdr.iter_mut().skip(1) {
+        if !record.iter().any(datatype::is_decimal_comma) {
+            continue;
+        }
+        let mut converted: StringRecord = record
+            .iter()
+            .map(|field| datatype::to_decimal_point(field).unwrap_or_else(|| field.to_string()))
+            .collect();
+        converted.set_position(record.position().cloned());
+        *record = converted;
+    }
+    rdr
+}
+
+// append the columns requested with --mutate. Later mutations may use earlier ones.
+fn mutate_records(
+    mut rdr: Vec<StringRecord>,
+    mutations: &[expr::Mutation],
+    opts: &datatype::FormatOptions,
+) -> Result<Vec<StringRecord>, String> {
+    for mutation in mutations {
+        let header = rdr[0].clone();
+        if header.iter().any(|h| h == mutation.name) {
+            return Err(format!(
+                "--mutate {}: there is already a column named \"{}\"",
+                mutation.name, mutation.name
+            ));
+        }
+        let mut indices = Vec::new();
+        for name in mutation.expr.columns() {
+            match header.iter().position(|h| h == name) {
+                Some(idx) => indices.push((name, idx)),
+                None => {
+                    return Err(format!(
+                        "--mutate {}: no column named \"{}\"",
+                        mutation.name, name
+                    ))
+                }
+            }
+        }
+        let cols = header.len();
+        for (i, record) in rdr.iter_mut().enumerate() {
+            crash::note_record(i, record.position().map(|pos| pos.line()), record.iter());
+            let value = if i == 0 {
+                mutation.name.clone()
+            } else {
+                let lookup = |name: &str| {
+                    let (_, idx) = indices.iter().find(|(n, _)| *n == name)?;
+                    let cell = record.get(*idx)?;
+                    if datatype::is_missing(cell, opts) {
+                        None
+                    } else {
+                        cell.trim().parse::<f64>().ok()
+                    }
+                };
+                mutation
+                    .expr
+                    .eval(&lookup)
+                    .map(|x| x.to_string())
+                    .unwrap_or_default()
+            };
+            // ragged rows are padded so the new value lines up with its header
+            while record.len() < cols {
+                record.push_field("");
+            }
+            record.push_field(&value);
         }
-        last = col + 1;
     }
-    last
+    Ok(rdr)
 }
 
-fn build_reader(opt: &Cli) -> Result<Reader<Box<dyn Read>>, std::io::Error> {
+fn build_reader(
+    opt: &Cli,
+    path: Option<&PathBuf>,
+) -> Result<Reader<Box<dyn Read>>, std::io::Error> {
     let mut delimiter = b',';
+    let mut is_html = false;
 
-    let source: Box<dyn Read> = if let Some(path) = &opt.file {
-        let file = File::open(path)?;
+    let mut source: Box<dyn Read> = if let Some(path) = path {
+        // URLs are streamed and detected by the path part of the URL, like local files.
+        let (file, path): (Box<dyn Read>, PathBuf) = if remote::is_url(path) {
+            (remote::open(path)?, remote::url_path(path))
+        } else if remote::is_object_url(path) {
+            (remote::open_object(path)?, remote::url_path(path))
+        } else {
+            (Box::new(BufReader::new(File::open(path)?)), path.clone())
+        };
+        let path = path.as_path();
 
         // Update the default delimiter by checking the file extension.
-        delimiter = match path.extension() {
-            Some(ext) if ext == "tsv" => b'\t',
-            Some(ext) if ext == "psv" => b'|',
-            _ => delimiter,
+        // For compressed files (data.tsv.gz) the extension under the compression one counts.
+        let ext = compression::strip_compression_extension(path);
+        let ext_delimiter = match ext.extension() {
+            Some(ext) if ext == "tsv" => Some(b'\t'),
+            Some(ext) if ext == "psv" => Some(b'|'),
+            _ => None,
         };
 
-        Box::new(BufReader::new(file))
+        let file = match compression::Compression::from_path(path) {
+            Some(compression) => compression.decoder(file)?,
+            None => file,
+        };
+        let (head, mut source) = format::peek(format::decode(file, opt.encoding))?;
+
+        // Other than tsv/psv the delimiter comes from the content: `data`, `export.txt`
+        // and European csv files separated by semicolons.
+        let content = format::Content::sniff(&head);
+        if let Some(message) = content.unsupported() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+        }
+        is_html = content == format::Content::Html
+            || matches!(ext.extension(), Some(ext) if ext == "html" || ext == "htm");
+
+        if is_html {
+            // the first table is converted to csv and read like any other file
+            let mut bytes = Vec::new();
+            source.read_to_end(&mut bytes)?;
+            let rows = html::first_table(&String::from_utf8_lossy(&bytes)).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "no <table> found in the HTML")
+            })?;
+            let csv = html::to_csv(&rows).map_err(io::Error::other)?;
+            Box::new(io::Cursor::new(csv))
+        } else {
+            let head = format::skip_lines(&head, rows_to_skip(opt));
+            delimiter = ext_delimiter
+                .or_else(|| format::sniff_delimiter(head, opt.comment))
+                .unwrap_or(delimiter);
+            source
+        }
+    } else if opt.clipboard {
+        // cells copied from a spreadsheet are tab separated, other text is sniffed
+        let text = read_clipboard()?;
+        let head = format::skip_lines(text.as_bytes(), rows_to_skip(opt));
+        delimiter = format::sniff_delimiter(head, opt.comment).unwrap_or(delimiter);
+        Box::new(io::Cursor::new(text.into_bytes()))
+    } else if let Some(encoding) = opt.encoding {
+        // stdin is sniffed in main, unless it has to be decoded first
+        let (head, source) = format::peek(format::decode(Box::new(io::stdin()), Some(encoding)))?;
+        let head = format::skip_lines(&head, rows_to_skip(opt));
+        delimiter = format::sniff_delimiter(head, opt.comment).unwrap_or(delimiter);
+        source
     } else {
-        Box::new(io::stdin())
+        // a byte order mark is still honored
+        format::decode(Box::new(io::stdin()), None)
     };
 
-    // Cli options take precedence.
-    if let Some(del) = opt.delimiter {
-        delimiter = del;
+    // Cli options take precedence, except for HTML which is always read as csv.
+    // The csv reader only splits on single bytes, so lines are split on longer
+    // delimiters up front and handed to it as csv.
+    match opt.delimiter.as_deref() {
+        Some(_) if is_html => {}
+        Some(&[del]) => delimiter = del,
+        Some(del) => {
+            source = Box::new(io::Cursor::new(split_lines(source, del, opt.comment)?));
+            delimiter = b',';
+        }
+        None => {}
     }
 
-    let reader = ReaderBuilder::new()
-        .flexible(!(opt.pedantic || opt.skip_invalid_rows))
-        .has_headers(false)
-        .delimiter(delimiter)
-        .from_reader(source);
+    let flexible = !(opt.pedantic || opt.skip_invalid_rows);
+    let reader = datatype::csv_reader(source, delimiter, flexible, opt.comment);
 
     Ok(reader)
 }
@@ -1210,28 +3083,29 @@ fn build_reader(opt: &Cli) -> Result<Reader<Box<dyn Read>>, std::io::Error> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use csv::ReaderBuilder;
 
     #[test]
     fn test_is_logical() {
-        assert_eq!(datatype::is_logical("T"), true);
-        assert_eq!(datatype::is_logical("t"), true);
-        assert_eq!(datatype::is_logical("F"), true);
-        assert_eq!(datatype::is_logical("f"), true);
-        assert_eq!(datatype::is_logical("TRUE"), true);
-        assert_eq!(datatype::is_logical("FALSE"), true);
-        assert_eq!(datatype::is_logical("True"), true);
-        assert_eq!(datatype::is_logical("False"), true);
-        assert_eq!(datatype::is_logical("true"), true);
-        assert_eq!(datatype::is_logical("false"), true);
+        assert!(datatype::is_logical("T"));
+        assert!(datatype::is_logical("t"));
+        assert!(datatype::is_logical("F"));
+        assert!(datatype::is_logical("f"));
+        assert!(datatype::is_logical("TRUE"));
+        assert!(datatype::is_logical("FALSE"));
+        assert!(datatype::is_logical("True"));
+        assert!(datatype::is_logical("False"));
+        assert!(datatype::is_logical("true"));
+        assert!(datatype::is_logical("false"));
     }
     #[test]
     fn test_is_na() {
-        assert_eq!(datatype::is_na(""), true);
-        assert_eq!(datatype::is_na("NA"), true);
-        assert_eq!(datatype::is_na("missing"), true);
-        assert_eq!(datatype::is_na("na"), true);
-        assert_eq!(datatype::is_na("1"), false);
-        assert_eq!(datatype::is_na("0"), false);
+        assert!(datatype::is_na(""));
+        assert!(datatype::is_na("NA"));
+        assert!(datatype::is_na("missing"));
+        assert!(datatype::is_na("na"));
+        assert!(!datatype::is_na("1"));
+        assert!(!datatype::is_na("0"));
     }
     // the following tests look messy, but the formatting is a necessary condition.
     #[test]
@@ -1292,12 +3166,12 @@ mod tests {
         let col_largest_width_post_proc: Vec<usize> = vec![16, 13, 4, 10];
         let mut vf: Vec<Vec<String>> = vec![vec!["#".to_string(); 13_usize]; 4_usize];
         for i in 0..col_largest_width_post_proc.len() {
-            vf[i] = datatype::format_strings(
-                &v[i],
-                col_largest_width_post_proc[i],
-                col_largest_width_post_proc[i],
-                3,
-            );
+            let opts = datatype::FormatOptions {
+                lower_column_width: col_largest_width_post_proc[i],
+                upper_column_width: col_largest_width_post_proc[i],
+                ..Default::default()
+            };
+            vf[i] = datatype::format_strings(&v[i], &opts);
         }
 
         assert_eq!(
@@ -1370,12 +3244,12 @@ mod tests {
         let col_largest_width_post_proc: Vec<usize> = vec![4, 4, 4, 4];
         let mut vf: Vec<Vec<String>> = vec![vec!["#".to_string(); 3_usize]; 4_usize];
         for i in 0..col_largest_width_post_proc.len() {
-            vf[i] = datatype::format_strings(
-                &v[i],
-                col_largest_width_post_proc[i],
-                col_largest_width_post_proc[i],
-                3,
-            );
+            let opts = datatype::FormatOptions {
+                lower_column_width: col_largest_width_post_proc[i],
+                upper_column_width: col_largest_width_post_proc[i],
+                ..Default::default()
+            };
+            vf[i] = datatype::format_strings(&v[i], &opts);
         }
 
         assert_eq!(
@@ -1403,12 +3277,12 @@ mod tests {
         let col_largest_width_post_proc: Vec<usize> = vec![7, 10, 20, 7, 7, 7, 7];
         let mut vf: Vec<Vec<String>> = vec![vec!["#".to_string(); 2_usize]; 7_usize];
         for i in 0..col_largest_width_post_proc.len() {
-            vf[i] = datatype::format_strings(
-                &v[i],
-                col_largest_width_post_proc[i],
-                col_largest_width_post_proc[i],
-                3,
-            );
+            let opts = datatype::FormatOptions {
+                lower_column_width: col_largest_width_post_proc[i],
+                upper_column_width: col_largest_width_post_proc[i],
+                ..Default::default()
+            };
+            vf[i] = datatype::format_strings(&v[i], &opts);
         }
 
         assert_eq!(
@@ -1428,54 +3302,771 @@ mod tests {
     #[test]
     fn build_reader_can_create_reader_without_file_specified() {
         let cli = Cli::from_args();
-        let reader = build_reader(&cli);
+        let reader = build_reader(&cli, None);
         assert!(reader.is_ok());
     }
 
+    #[test]
+    fn terminal_size_falls_back_to_env_then_default() {
+        let failed = || Err(io::Error::other("no tty"));
+        assert_eq!(terminal_size(Ok((120, 40)), None, None), (120, 40));
+        assert_eq!(
+            terminal_size(failed(), Some("100".into()), Some("50".into())),
+            (100, 50)
+        );
+        assert_eq!(terminal_size(failed(), Some("wide".into()), None), (80, 24));
+        assert_eq!(terminal_size(Ok((0, 0)), None, Some("30".into())), (80, 30));
+    }
+
+    #[test]
+    fn filters_rows_by_na() {
+        let rdr = vec![
+            StringRecord::from(vec!["a", "b"]),
+            StringRecord::from(vec!["1", "2"]),
+            StringRecord::from(vec!["NA", "2"]),
+            StringRecord::from(vec!["", "null"]),
+        ];
+        let opts = datatype::FormatOptions::default();
+        let first_cells = |rows: Vec<StringRecord>| -> Vec<String> {
+            rows.iter().map(|r| r[0].to_string()).collect()
+        };
+        assert_eq!(
+            first_cells(filter_na_rows(rdr.clone(), NaHow::Any, true, &opts)),
+            vec!["a", "NA", ""]
+        );
+        assert_eq!(
+            first_cells(filter_na_rows(rdr.clone(), NaHow::All, true, &opts)),
+            vec!["a", ""]
+        );
+        assert_eq!(
+            first_cells(filter_na_rows(rdr.clone(), NaHow::Any, false, &opts)),
+            vec!["a", "1"]
+        );
+        let literal = datatype::FormatOptions {
+            na_strings: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            first_cells(filter_na_rows(rdr, NaHow::Any, true, &literal)),
+            vec!["a", ""]
+        );
+    }
+
+    #[test]
+    fn combines_files_with_a_file_column() {
+        let mut rdr = Vec::new();
+        let file = |rows: &[&[&str]]| -> Vec<StringRecord> {
+            rows.iter()
+                .map(|r| StringRecord::from(r.to_vec()))
+                .collect()
+        };
+        append_file_records(
+            &mut rdr,
+            file(&[&["a", "b"], &["1", "2"]]),
+            Path::new("x.csv"),
+        )
+        .unwrap();
+        append_file_records(&mut rdr, file(&[]), Path::new("empty.csv")).unwrap();
+        append_file_records(
+            &mut rdr,
+            file(&[&["a", "b"], &["3", "4"]]),
+            Path::new("y.csv"),
+        )
+        .unwrap();
+        assert_eq!(
+            rdr,
+            file(&[
+                &["file", "a", "b"],
+                &["x.csv", "1", "2"],
+                &["y.csv", "3", "4"]
+            ])
+        );
+        assert!(append_file_records(&mut rdr, file(&[&["a", "c"]]), Path::new("z.csv")).is_err());
+    }
+
+    #[test]
+    fn config_and_cli_precedence() {
+        let cli = |args: &[&str]| Cli::from_iter(std::iter::once("tv").chain(args.iter().copied()));
+        let options = |values: (bool, usize, bool, &str, &str, usize, usize, usize)| Options {
+            extend_width_length: values.0,
+            gutter_width: values.1,
+            leading_newline: values.2,
+            title: values.3.to_string(),
+            footer: values.4.to_string(),
+            row_display: values.5,
+            lower_column_width: values.6,
+            upper_column_width: values.7,
+        };
+        let no_config = Config::default();
+        let config = Config {
+            title: Some("config title".to_string()),
+            footer: Some("config footer".to_string()),
+            number: Some(10),
+            extend_width_length: Some(true),
+            gutter_width: Some(4),
+            leading_newline: Some(false),
+            lower_column_width: Some(3),
+            upper_column_width: Some(30),
+            ..Config::default()
+        };
+        let args = [
+            "-e",
+            "--gutter-width",
+            "3",
+            "--no-leading-newline",
+            "-t",
+            "cli title",
+            "-F",
+            "cli footer",
+            "-n",
+            "50",
+            "-l",
+            "4",
+            "-u",
+            "40",
+        ];
+        let from_cli = options((true, 3, false, "cli title", "cli footer", 50, 4, 40));
+
+        // neither: the defaults
+        assert_eq!(
+            resolve_options(&no_config, &cli(&[])),
+            options((false, 6, true, "NA", "NA", 25, 2, 20))
+        );
+        // config only
+        assert_eq!(
+            resolve_options(&config, &cli(&[])),
+            options((true, 4, false, "config title", "config footer", 10, 3, 30))
+        );
+        // cli only, and cli over config
+        assert_eq!(resolve_options(&no_config, &cli(&args)), from_cli);
+        assert_eq!(resolve_options(&config, &cli(&args)), from_cli);
+        // one cli option does not hide the config of the others
+        let footer_only = resolve_options(&config, &cli(&["-F", "cli footer"]));
+        assert_eq!(footer_only.title, "config title");
+        assert_eq!(footer_only.footer, "cli footer");
+        assert_eq!(footer_only.row_display, 10);
+        let upper_only = resolve_options(&config, &cli(&["-u", "25"]));
+        assert_eq!(upper_only.lower_column_width, 3);
+        assert_eq!(upper_only.upper_column_width, 25);
+        // a cli value equal to the default still wins over the config
+        let defaults = [
+            "--no-extend-width-and-length",
+            "--gutter-width",
+            "6",
+            "-n",
+            "25",
+            "-l",
+            "2",
+            "-u",
+            "20",
+        ];
+        let explicit = resolve_options(&config, &cli(&defaults));
+        assert!(!explicit.extend_width_length);
+        assert_eq!(
+            (
+                explicit.gutter_width,
+                explicit.row_display,
+                explicit.lower_column_width,
+                explicit.upper_column_width
+            ),
+            (6, 25, 2, 20)
+        );
+        assert!(Cli::from_iter_safe(["tv", "-e", "--no-extend-width-and-length"]).is_err());
+    }
+
+    #[test]
+    fn reports_the_columns_that_fit() {
+        let v = vec![vec!["carat", "0.23"], vec!["cut", "Ideal"]];
+        let vp = vec![
+            vec!["carat ".to_string(), "cut   ".to_string()],
+            vec!["0.23  ".to_string(), "Ideal ".to_string()],
+        ];
+        assert_eq!(
+            fit_report(&v, &vp, 1, 16, 8).to_string(),
+            concat!(
+                r#"{"dropped":[{"name":"cut","width":6}],"gutter_width":8,"#,
+                r#""shown":[{"name":"carat","width":6}],"terminal_width":16}"#
+            )
+        );
+    }
+
+    #[test]
+    fn digests_the_shown_rows() {
+        let v = vec![vec!["name", "Ann"], vec!["age", "7"]];
+        assert_eq!(
+            digest(&v),
+            "bf073c4164d1b7d3874be54a21afc4a3ce68192511b658168087dd3db33bd97f"
+        );
+    }
+
+    #[test]
+    fn selects_columns_by_name_and_index() {
+        let rdr = vec![
+            StringRecord::from(vec!["name", "age", "2"]),
+            StringRecord::from(vec!["Ann", "7", "x"]),
+        ];
+        let select = |columns: &[&str]| {
+            let columns: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+            select_columns(rdr.clone(), &columns)
+        };
+        assert_eq!(
+            select(&["age", "1"]),
+            Ok(vec![
+                StringRecord::from(vec!["age", "name"]),
+                StringRecord::from(vec!["7", "Ann"]),
+            ])
+        );
+        assert_eq!(
+            select(&["2"]),
+            Ok(vec![
+                StringRecord::from(vec!["2"]),
+                StringRecord::from(vec!["x"]),
+            ])
+        );
+        assert_eq!(
+            select(&["height"]),
+            Err("--select: no column named \"height\"".to_string())
+        );
+        assert_eq!(
+            select(&["0"]),
+            Err("--select 0: there are 3 columns, counting from 1".to_string())
+        );
+    }
+
+    #[test]
+    fn drops_columns_by_name_and_index() {
+        let rdr = vec![
+            StringRecord::from(vec!["name", "age", "ticket"]),
+            StringRecord::from(vec!["Ann", "7", "A/5 21171"]),
+        ];
+        let drop = |columns: &[&str]| {
+            let columns: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+            drop_columns(rdr.clone(), &columns)
+        };
+        assert_eq!(
+            drop(&["ticket", "1"]),
+            Ok(vec![
+                StringRecord::from(vec!["age"]),
+                StringRecord::from(vec!["7"]),
+            ])
+        );
+        assert_eq!(
+            drop(&["height"]),
+            Err("--drop: no column named \"height\"".to_string())
+        );
+    }
+
+    #[test]
+    fn selects_columns_matching_a_regex() {
+        let rdr = vec![
+            StringRecord::from(vec!["sales_q1", "region", "sales_q2"]),
+            StringRecord::from(vec!["10", "north", "12"]),
+        ];
+        let select = |pattern: &str| {
+            select_matching_columns(rdr.clone(), &regex::Regex::new(pattern).unwrap())
+        };
+        assert_eq!(
+            select("^sales_"),
+            Ok(vec![
+                StringRecord::from(vec!["sales_q1", "sales_q2"]),
+                StringRecord::from(vec!["10", "12"]),
+            ])
+        );
+        assert_eq!(
+            select("^cost"),
+            Err("--select-regex: no column name matches \"^cost\"".to_string())
+        );
+    }
+
+    #[test]
+    fn windows_rows_for_tail_and_range() {
+        let range = |s: &str| s.parse::<RowRange>();
+        let ends = |s: &str| range(s).map(|r| (r.start, r.end));
+        assert_eq!(ends("100:150"), Ok((100, Some(150))));
+        assert_eq!(ends(":150"), Ok((1, Some(150))));
+        assert_eq!(ends("100:"), Ok((100, None)));
+        assert!(range("150:100").is_err());
+        assert!(range("0:10").is_err());
+        assert!(range("100").is_err());
+
+        assert_eq!(row_window(1000, 0, None, None), (0, None));
+        assert_eq!(row_window(1000, 1500, None, None), (1000, None));
+        assert_eq!(row_window(1000, 0, Some(10), None), (990, Some(10)));
+        assert_eq!(row_window(5, 0, Some(10), None), (0, Some(10)));
+        let window = |s: &str| row_window(1000, 0, None, range(s).ok());
+        assert_eq!(window("100:150"), (99, Some(51)));
+        assert_eq!(window("991:"), (990, Some(1000)));
+    }
+
+    #[test]
+    fn presets_fill_in_options_not_given() {
+        let preset = |args: &[&str]| {
+            let mut opt = Cli::from_iter(["tv"].iter().chain(args));
+            if let Some(preset) = opt.preset {
+                preset.apply(&mut opt);
+            }
+            opt
+        };
+        let opt = preset(&["--preset", "github"]);
+        assert_eq!(opt.output_format, Some(output::OutputFormat::Markdown));
+        assert_eq!((opt.upper_column_width, opt.sigfig), (Some(30), Some(4)));
+        assert!(opt.all_columns);
+
+        let opt = preset(&["--preset", "slack", "-u", "10", "-g", "5"]);
+        assert_eq!(opt.output_format, Some(output::OutputFormat::Table));
+        assert_eq!((opt.upper_column_width, opt.sigfig), (Some(10), Some(5)));
+        assert!(opt.no_leading_newline);
+        assert_eq!(opt.preset.and_then(Preset::width), Some(80));
+
+        let opt = preset(&["--preset", "email", "--output-format", "latex"]);
+        assert_eq!(opt.output_format, Some(output::OutputFormat::Latex));
+
+        // explicit default values win over the preset too
+        let args = ["--preset", "github", "-g", "3", "-u", "20"];
+        let opt = preset(&[&args[..], &["--output-format", "table"]].concat());
+        assert_eq!(opt.output_format, Some(output::OutputFormat::Table));
+        assert_eq!((opt.upper_column_width, opt.sigfig), (Some(20), Some(3)));
+        assert!("teams".parse::<Preset>().is_err());
+    }
+
+    #[test]
+    fn sorts_records_by_a_column() {
+        let rdr = vec![
+            StringRecord::from(vec!["name", "price"]),
+            StringRecord::from(vec!["b", "10"]),
+            StringRecord::from(vec!["a", "NA"]),
+            StringRecord::from(vec!["c", "9.5"]),
+            StringRecord::from(vec!["d", "-2"]),
+        ];
+        let opts = datatype::FormatOptions::default();
+        let sorted = |key: &str| {
+            let (rdr, idx) = sort_records(rdr.clone(), &key.parse().unwrap(), &opts).unwrap();
+            let names: Vec<&str> = rdr[1..].iter().map(|r| &r[0]).collect();
+            (names.concat(), idx)
+        };
+        assert_eq!(sorted("price"), ("dcba".to_string(), 1));
+        assert_eq!(sorted("price:desc"), ("bcda".to_string(), 1));
+        assert_eq!(sorted("1:desc"), ("dcba".to_string(), 0));
+        assert_eq!(sorted("name:asc"), ("abcd".to_string(), 0));
+        assert_eq!(
+            sort_records(rdr.clone(), &"size".parse().unwrap(), &opts),
+            Err("--sort: no column named \"size\"".to_string())
+        );
+        assert!(":desc".parse::<SortKey>().is_err());
+    }
+
+    #[test]
+    fn removes_duplicate_rows() {
+        let rdr = vec![
+            StringRecord::from(vec!["cut", "price"]),
+            StringRecord::from(vec!["Ideal", "10"]),
+            StringRecord::from(vec!["Good", "10"]),
+            StringRecord::from(vec!["Ideal", "10"]),
+            StringRecord::from(vec!["Ideal", "12"]),
+        ];
+        let (unique, duplicates) = unique_records(rdr.clone(), &[]).unwrap();
+        assert_eq!(unique, [&rdr[..3], &rdr[4..]].concat());
+        assert_eq!(duplicates, 1);
+        let (unique, duplicates) = unique_records(rdr.clone(), &["cut".to_string()]).unwrap();
+        assert_eq!(unique, rdr[..3].to_vec());
+        assert_eq!(duplicates, 2);
+        let (unique, duplicates) = unique_records(rdr.clone(), &["2".to_string()]).unwrap();
+        assert_eq!(unique, [&rdr[..2], &rdr[4..]].concat());
+        assert_eq!(duplicates, 2);
+        assert_eq!(
+            unique_records(rdr, &["size".to_string()]),
+            Err("--unique-by: no column named \"size\"".to_string())
+        );
+    }
+
+    #[test]
+    fn counts_the_values_of_a_column() {
+        let rdr = vec![
+            StringRecord::from(vec!["city", "n"]),
+            StringRecord::from(vec!["Oslo", "1"]),
+            StringRecord::from(vec!["Lima", "2"]),
+            StringRecord::from(vec!["NA", "3"]),
+            StringRecord::from(vec!["Lima", "4"]),
+        ];
+        let opts = datatype::FormatOptions::default();
+        let counted = count_values(rdr.clone(), "city", &opts).unwrap();
+        assert_eq!(
+            counted,
+            vec![
+                StringRecord::from(vec!["city", "n", "percent"]),
+                StringRecord::from(vec!["Lima", "2", "50.00"]),
+                StringRecord::from(vec!["Oslo", "1", "25.00"]),
+                StringRecord::from(vec!["", "1", "25.00"]),
+            ]
+        );
+        let literal = datatype::FormatOptions {
+            na_strings: false,
+            ..Default::default()
+        };
+        let counted = count_values(rdr.clone(), "1", &literal).unwrap();
+        assert_eq!(counted[3], StringRecord::from(vec!["NA", "1", "25.00"]));
+        assert_eq!(
+            count_values(rdr, "town", &opts),
+            Err("--count: no column named \"town\"".to_string())
+        );
+    }
+
+    #[test]
+    fn counts_missing_values_per_column() {
+        let rdr = vec![
+            StringRecord::from(vec!["a", "b", "c"]),
+            StringRecord::from(vec!["1", "NA", ""]),
+            StringRecord::from(vec!["2", "x"]),
+            StringRecord::from(vec!["null", "n/a", "3"]),
+        ];
+        let opts = datatype::FormatOptions::default();
+        assert_eq!(na_counts(&rdr, &opts), vec![1, 2, 2]);
+        let literal = datatype::FormatOptions {
+            na_strings: false,
+            ..Default::default()
+        };
+        assert_eq!(na_counts(&rdr, &literal), vec![0, 0, 2]);
+    }
+
+    #[test]
+    fn finds_duplicate_column_names() {
+        let header = ["id", "x", "y", "x", "z", "y", "x"];
+        assert_eq!(duplicate_names(header.into_iter()), ["x", "y"]);
+        assert!(duplicate_names(["a", "b"].into_iter()).is_empty());
+    }
+
+    #[test]
+    fn flags_mostly_missing_columns() {
+        let opts = datatype::FormatOptions::default();
+        let column = ["a", "1", "NA", "", "4"];
+        assert!(mostly_missing(&column, 49.0, &opts));
+        assert!(!mostly_missing(&column, 50.0, &opts));
+        assert!(mostly_missing(&["b", "NA"], 99.5, &opts));
+        assert!(!mostly_missing(&["c"], 0.0, &opts));
+    }
+
+    #[test]
+    fn skipping_invalid_rows_keeps_the_order_of_the_rest() {
+        let text = "a,b\n1,2\n3\n4,5\n6,7,8\n\"9\n\",10\n11,\n12\n";
+        let records = ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(text.as_bytes())
+            .records()
+            .collect::<Vec<_>>();
+        let (valid, skipped) = keep_valid_records(records);
+        assert_eq!(
+            valid,
+            vec![
+                StringRecord::from(vec!["a", "b"]),
+                StringRecord::from(vec!["1", "2"]),
+                StringRecord::from(vec!["4", "5"]),
+                StringRecord::from(vec!["9\n", "10"]),
+                StringRecord::from(vec!["11", ""]),
+            ]
+        );
+        assert_eq!(skipped, vec![3, 5, 9]);
+    }
+
+    #[test]
+    fn shows_proportions_as_percentages() {
+        let mut rdr = vec![
+            StringRecord::from(vec!["share", "n"]),
+            StringRecord::from(vec!["0.153", "0.5"]),
+            StringRecord::from(vec!["NA", "1"]),
+        ];
+        to_percents(&mut rdr, &["share".to_string()]).unwrap();
+        assert_eq!(rdr[1], StringRecord::from(vec!["15.3%", "0.5"]));
+        assert_eq!(rdr[2], StringRecord::from(vec!["NA", "1"]));
+        assert!(to_percents(&mut rdr, &["total".to_string()]).is_err());
+    }
+
+    #[test]
+    fn shows_date_columns_as_iso_dates() {
+        let mut rdr = vec![
+            StringRecord::from(vec!["day", "id", "note"]),
+            StringRecord::from(vec!["01/31/2024", "20240131", "Jan 2, 2024"]),
+            StringRecord::from(vec!["NA", "12", "soon"]),
+            StringRecord::from(vec!["2/1/2024", "20240201", ""]),
+        ];
+        rdr[1].set_position(Some(csv::Position::new()));
+        let literal_na = datatype::FormatOptions {
+            na_strings: false,
+            ..Default::default()
+        };
+        let mut literal = rdr.clone();
+        to_iso_dates(&mut literal, &literal_na);
+        // with --literal-na the NA of the day column is a value, not a date
+        assert_eq!(literal[1][0], *"01/31/2024");
+        to_iso_dates(&mut rdr, &datatype::FormatOptions::default());
+        assert_eq!(
+            rdr[1],
+            StringRecord::from(vec!["2024-01-31", "20240131", "Jan 2, 2024"])
+        );
+        assert_eq!(rdr[2], StringRecord::from(vec!["NA", "12", "soon"]));
+        assert_eq!(
+            rdr[3],
+            StringRecord::from(vec!["2024-02-01", "20240201", ""])
+        );
+        assert!(rdr[1].position().is_some());
+    }
+
+    #[test]
+    fn shows_date_times_in_a_zone() {
+        let mut rdr = vec![
+            StringRecord::from(vec!["at", "n"]),
+            StringRecord::from(vec!["2020-10-09T23:59:37.5Z", "1"]),
+            StringRecord::from(vec!["2020-10-09 08:00:00-04:00", "2"]),
+            StringRecord::from(vec!["NA", "3"]),
+        ];
+        to_date_times(&mut rdr, "+01:00".parse().ok());
+        assert_eq!(rdr[1][0], *"2020-10-10 00:59:37+01:00");
+        assert_eq!(rdr[2][0], *"2020-10-09 13:00:00+01:00");
+        assert_eq!(rdr[3], StringRecord::from(vec!["NA", "3"]));
+    }
+
+    #[test]
+    fn shows_epoch_timestamps_as_date_times() {
+        let rdr = vec![
+            StringRecord::from(vec!["id", "created_ts", "seen"]),
+            StringRecord::from(vec!["1602244777", "1602244777", "1602244777250"]),
+            StringRecord::from(vec!["1602244778", "NA", "0"]),
+        ];
+        let opts = datatype::FormatOptions::default();
+        let mut detected = rdr.clone();
+        from_epochs(&mut detected, &[], true, None, &opts).unwrap();
+        assert_eq!(
+            detected[1],
+            StringRecord::from(vec!["1602244777", "2020-10-09 11:59:37Z", "1602244777250"])
+        );
+        assert_eq!(detected[2][1], *"NA");
+
+        let mut named = rdr.clone();
+        from_epochs(&mut named, &["seen".to_string()], false, None, &opts).unwrap();
+        assert_eq!(named[1][1], *"1602244777");
+        assert_eq!(named[1][2], *"2020-10-09 11:59:37Z");
+        assert_eq!(named[2][2], *"1970-01-01 00:00:00Z");
+
+        let mut unknown = rdr;
+        assert!(from_epochs(&mut unknown, &["when".to_string()], false, None, &opts).is_err());
+    }
+
+    #[test]
+    fn reads_decimal_commas() {
+        let mut rdr = vec![
+            StringRecord::from(vec!["price", "name"]),
+            StringRecord::from(vec!["3,14", "Ann"]),
+            StringRecord::from(vec!["1.234,5", "Bo, Jr"]),
+            StringRecord::from(vec!["12", "x"]),
+        ];
+        rdr[1].set_position(Some(csv::Position::new()));
+        let rdr = to_decimal_points(rdr);
+        assert_eq!(rdr[1], StringRecord::from(vec!["3.14", "Ann"]));
+        assert_eq!(rdr[2], StringRecord::from(vec!["1234.5", "Bo, Jr"]));
+        assert_eq!(rdr[3], StringRecord::from(vec!["12", "x"]));
+        assert!(rdr[1].position().is_some());
+    }
+
+    #[test]
+    fn drops_empty_and_constant_columns() {
+        let rdr = vec![
+            StringRecord::from(vec!["id", "empty", "same", "na_or_x"]),
+            StringRecord::from(vec!["1", "", "x", "x"]),
+            StringRecord::from(vec!["2", "NA", "x", "NA"]),
+        ];
+        let opts = datatype::FormatOptions::default();
+        let names = |rdr: &[StringRecord]| rdr[0].iter().collect::<Vec<_>>().join(",");
+        let dropped = drop_uninformative_columns(rdr.clone(), false, &opts).unwrap();
+        assert_eq!(names(&dropped), "id,same,na_or_x");
+        let dropped = drop_uninformative_columns(rdr.clone(), true, &opts).unwrap();
+        assert_eq!(names(&dropped), "id,na_or_x");
+        assert_eq!(dropped[2], StringRecord::from(vec!["2", "NA"]));
+        assert!(drop_uninformative_columns(rdr[..2].to_vec(), true, &opts).is_err());
+        let header = rdr[..1].to_vec();
+        assert_eq!(
+            drop_uninformative_columns(header.clone(), true, &opts),
+            Ok(header)
+        );
+    }
+
+    #[test]
+    fn mutates_records() {
+        let rdr = vec![
+            StringRecord::from(vec!["a", "b"]),
+            StringRecord::from(vec!["1", "2"]),
+            StringRecord::from(vec!["NA", "4"]),
+            StringRecord::from(vec!["5"]),
+        ];
+        let mutations = [
+            expr::parse_mutation("c = a + b").unwrap(),
+            expr::parse_mutation("d = c * 2").unwrap(),
+        ];
+        let opts = datatype::FormatOptions::default();
+        let rdr = mutate_records(rdr, &mutations, &opts).unwrap();
+        assert_eq!(rdr[0], StringRecord::from(vec!["a", "b", "c", "d"]));
+        assert_eq!(rdr[1], StringRecord::from(vec!["1", "2", "3", "6"]));
+        assert_eq!(rdr[2], StringRecord::from(vec!["NA", "4", "", ""]));
+        assert_eq!(rdr[3], StringRecord::from(vec!["5", "", "", ""]));
+    }
+
+    #[test]
+    fn mutations_need_known_columns_and_new_names() {
+        let rdr = vec![
+            StringRecord::from(vec!["a", "b"]),
+            StringRecord::from(vec!["1", "2"]),
+        ];
+        let opts = datatype::FormatOptions::default();
+        let unknown = [expr::parse_mutation("c = a + z").unwrap()];
+        assert_eq!(
+            mutate_records(rdr.clone(), &unknown, &opts),
+            Err("--mutate c: no column named \"z\"".to_string())
+        );
+        let existing = [expr::parse_mutation("b = a + 1").unwrap()];
+        assert_eq!(
+            mutate_records(rdr.clone(), &existing, &opts),
+            Err("--mutate b: there is already a column named \"b\"".to_string())
+        );
+        let twice = [
+            expr::parse_mutation("c = a + 1").unwrap(),
+            expr::parse_mutation("c = a + 2").unwrap(),
+        ];
+        assert!(mutate_records(rdr, &twice, &opts).is_err());
+    }
+
+    #[test]
+    fn transposes_records() {
+        let records = vec![
+            StringRecord::from(vec!["name", "age"]),
+            StringRecord::from(vec!["Ann", "7"]),
+            StringRecord::from(vec!["Bob"]),
+        ];
+        assert_eq!(
+            transpose(&records),
+            vec![
+                StringRecord::from(vec!["column", "1", "2"]),
+                StringRecord::from(vec!["name", "Ann", "Bob"]),
+                StringRecord::from(vec!["age", "7", ""]),
+            ]
+        );
+        assert_eq!(
+            transpose(&records[..1]),
+            vec![
+                StringRecord::from(vec!["column"]),
+                StringRecord::from(vec!["name"]),
+                StringRecord::from(vec!["age"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn row_display_is_clamped() {
+        assert_eq!(rows_to_show(14, Some(0)), 1);
+        assert_eq!(rows_to_show(14, Some(5)), 6);
+        assert_eq!(rows_to_show(14, Some(13)), 14);
+        assert_eq!(rows_to_show(14, Some(100)), 14);
+        assert_eq!(rows_to_show(14, Some(usize::MAX)), 14);
+        assert_eq!(rows_to_show(14, None), 14);
+        assert_eq!(rows_to_show(1, Some(0)), 1);
+    }
+
+    #[test]
+    fn gutter_fits_the_last_row_number() {
+        assert_eq!(fit_gutter_width(6, 25), 6);
+        assert_eq!(fit_gutter_width(6, 999_999), 6);
+        assert_eq!(fit_gutter_width(6, 1_000_000), 7);
+        assert_eq!(fit_gutter_width(2, 150), 3);
+        assert_eq!(fit_gutter_width(0, 1_000_000), 0);
+    }
+
+    #[test]
+    fn synthesizes_column_names() {
+        let records = vec![
+            StringRecord::from(vec!["1", "a"]),
+            StringRecord::from(vec!["2", "b", "extra"]),
+        ];
+        assert_eq!(
+            synthesize_header(&records),
+            StringRecord::from(vec!["X1", "X2", "X3"])
+        );
+    }
+
+    #[test]
+    fn explains_why_a_file_does_not_open() {
+        let missing = io::Error::from(io::ErrorKind::NotFound);
+        assert_eq!(
+            open_error_message(Path::new("nofile.csv"), &missing),
+            "nofile.csv: no such file. Check the spelling and the current directory."
+        );
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(open_error_message(Path::new("secret.csv"), &denied)
+            .starts_with("secret.csv: permission denied."));
+        assert!(open_error_message(Path::new("src"), &denied).starts_with("src is a directory."));
+        let binary = io::Error::new(io::ErrorKind::InvalidData, "binary data");
+        assert_eq!(
+            open_error_message(Path::new("export"), &binary),
+            "export: binary data"
+        );
+    }
+
+    #[test]
+    fn splits_lines_on_multi_byte_delimiters() {
+        let input = &b"name||note\r\nAnn||\"hi\", she said\n\nBob||\n"[..];
+        assert_eq!(
+            split_lines(input, b"||", None).unwrap(),
+            b"name,note\nAnn,\"\"\"hi\"\", she said\"\n\nBob,\n"
+        );
+        let input = &b"a\x01b\n1\x012\n"[..];
+        assert_eq!(split_lines(input, b"\x01", None).unwrap(), b"a,b\n1,2\n");
+        let input = &b"# a||b, c\nx||y\n"[..];
+        assert_eq!(
+            split_lines(input, b"||", Some(b'#')).unwrap(),
+            b"# a||b, c\nx,y\n"
+        );
+    }
+
     #[test]
     fn test_is_number() {
         // Integers
-        assert_eq!(datatype::is_number("12345"), true);
-        assert_eq!(datatype::is_number("   12345"), true);
-        assert_eq!(datatype::is_number("12345   "), true);
-        assert_eq!(datatype::is_number("   12345   "), true);
-        assert_eq!(datatype::is_number("-12345"), true);
-        assert_eq!(datatype::is_number("   -12345"), true);
-        assert_eq!(datatype::is_number("-12345   "), true);
-        assert_eq!(datatype::is_number("   -12345   "), true);
+        assert!(datatype::is_number("12345"));
+        assert!(datatype::is_number("   12345"));
+        assert!(datatype::is_number("12345   "));
+        assert!(datatype::is_number("   12345   "));
+        assert!(datatype::is_number("-12345"));
+        assert!(datatype::is_number("   -12345"));
+        assert!(datatype::is_number("-12345   "));
+        assert!(datatype::is_number("   -12345   "));
 
         // Doubles
-        assert_eq!(datatype::is_number("123.45"), true);
-        assert_eq!(datatype::is_number("   123.45"), true);
-        assert_eq!(datatype::is_number("123.45   "), true);
-        assert_eq!(datatype::is_number("   123.45   "), true);
-        assert_eq!(datatype::is_number("0."), true);
-        assert_eq!(datatype::is_number(".0"), true);
-        assert_eq!(datatype::is_number("-123.45"), true);
-        assert_eq!(datatype::is_number("   -123.45"), true);
-        assert_eq!(datatype::is_number("-123.45   "), true);
-        assert_eq!(datatype::is_number("   -123.45   "), true);
+        assert!(datatype::is_number("123.45"));
+        assert!(datatype::is_number("   123.45"));
+        assert!(datatype::is_number("123.45   "));
+        assert!(datatype::is_number("   123.45   "));
+        assert!(datatype::is_number("0."));
+        assert!(datatype::is_number(".0"));
+        assert!(datatype::is_number("-123.45"));
+        assert!(datatype::is_number("   -123.45"));
+        assert!(datatype::is_number("-123.45   "));
+        assert!(datatype::is_number("   -123.45   "));
 
         // Misc
-        assert_eq!(datatype::is_number("123text"), false);
-        assert_eq!(datatype::is_number("text123"), false);
-        assert_eq!(datatype::is_number("123.123.123"), false);
+        assert!(!datatype::is_number("123text"));
+        assert!(!datatype::is_number("text123"));
+        assert!(!datatype::is_number("123.123.123"));
     }
 
     #[test]
     fn test_is_negative_number() {
-        assert_eq!(datatype::is_negative_number("-12345"), true);
-        assert_eq!(datatype::is_negative_number("   -12345"), true);
-        assert_eq!(datatype::is_negative_number("-12345   "), true);
-        assert_eq!(datatype::is_negative_number("   -12345   "), true);
-        assert_eq!(datatype::is_negative_number("-12.345"), true);
-        assert_eq!(datatype::is_negative_number("   -12.345"), true);
-        assert_eq!(datatype::is_negative_number("-12.345   "), true);
-        assert_eq!(datatype::is_negative_number("   -12.345   "), true);
-        assert_eq!(datatype::is_negative_number("0.0"), false);
-        assert_eq!(datatype::is_negative_number("0."), false);
-        assert_eq!(datatype::is_negative_number("text"), false);
-        assert_eq!(datatype::is_negative_number("-123.123.123"), false);
+        assert!(datatype::is_negative_number("-12345"));
+        assert!(datatype::is_negative_number("   -12345"));
+        assert!(datatype::is_negative_number("-12345   "));
+        assert!(datatype::is_negative_number("   -12345   "));
+        assert!(datatype::is_negative_number("-12.345"));
+        assert!(datatype::is_negative_number("   -12.345"));
+        assert!(datatype::is_negative_number("-12.345   "));
+        assert!(datatype::is_negative_number("   -12.345   "));
+        assert!(!datatype::is_negative_number("0.0"));
+        assert!(!datatype::is_negative_number("0."));
+        assert!(!datatype::is_negative_number("text"));
+        assert!(!datatype::is_negative_number("-123.123.123"));
     }
 
     #[test]
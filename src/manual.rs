@@ -0,0 +1,342 @@
+// The manual of tv, a man page or text for a pager, built from the --help of
+// the command line options and the sections below.
+use unicode_width::UnicodeWidthStr;
+
+const DESCRIPTION: &str = "Tidy Viewer (tv) is a csv pretty printer that uses column styling to \
+maximize viewer enjoyment. Numbers are shown to a few significant figures and lined up on their \
+decimal point, missing values are shown as NA and columns that do not fit the terminal are named \
+below the table.";
+
+const CONFIGURATION: &str = r##"Options can also be set in a tv.toml config file. Options given on the command line take precedence. Check the options you have set with `tv --config-details`. The location of tv.toml depends on the OS:
+* Linux: $XDG_CONFIG_HOME or $HOME/.config/tv.toml
+* macOS: $HOME/Library/Application Support/tv.toml
+* Windows: {FOLDERID_RoamingAppData}\tv.toml
+An example config, remove the first column of comments for a valid toml file:
+
+    ## ==Tidy-Viewer Config Example==
+    ## The delimiter separating the columns. [default: ,]
+    #delimiter = ","
+    ## Add a title to your tv. Example 'Test Data' [default: NA ("")]
+    #title = ""
+    ## Add a footer to your tv. Example 'footer info' [default: NA ("")]
+    #footer = ""
+    ## The upper (maximum) width of columns. [default: 20]
+    #upper_column_width = 20
+    ## The minimum width of columns. Must be 2 or larger. [default: 2]
+    #lower_column_width = 2
+    ## head number of rows to output <row-display> [default: 25]
+    #number = 35
+    ## extend width and length in terms of the number of rows and columns displayed beyond term width [default: false]
+    # extend_width_length = true
+    ## meta_color = [R,G,B] color for row index and "tv dim: rows x cols"
+    #meta_color = [64, 179, 162]
+    ## header_color = [R,G,B] color for column headers
+    #header_color = [232, 168, 124]
+    ## std_color = [R,G,B] color for standard cell data values
+    #std_color = [133, 205, 202]
+    ## na_color = [R,G,B] color for NA values
+    #na_color = [226, 125, 95]
+    ## neg_num_color = [R,G,B] color for negative values
+    #neg_num_color = [226, 125, 95]
+    ## highlight_color = [R,G,B] color for the matches of --highlight
+    #highlight_color = [235, 203, 139]
+    ## width of the row number gutter. 0 removes the gutter [default: 6]
+    #gutter_width = 6
+    ## print a blank line before the output [default: true]
+    #leading_newline = true
+    ## skip lines starting with this character, like metadata in scientific exports [default: none]
+    #comment = "#"
+    ## the strings that are missing values, instead of the standard ones like NA, null and None
+    #na_values = ["", "NA", "NULL"]"##;
+
+const COLORS: &str = r#"Each part of the table has a color role, set by the theme of -c or by tv.toml:
+* meta_color: row numbers, the tv dim line and the notes below the table
+* header_color: column names
+* std_color: values
+* na_color: missing values, shown as NA, and the counts of --na-report
+* neg_num_color: negative numbers
+* highlight_color: the matches of --highlight
+The themes are (1) nord, the default, (2) one_dark, (3) gruvbox, (4) dracula and (5) solarized light. Colors in tv.toml are used unless a theme is picked with -c. Colors are only shown on a terminal, or with -a. Use --color-depth 16 for terminals without truecolor."#;
+
+const EXAMPLES: &str = r#"Show a file, or what is piped in:
+
+    tv diamonds.csv
+    cat diamonds.csv | head -n 35 | tv
+
+Page through every row and column, with color:
+
+    tv -e -a diamonds.csv | less -RS
+
+Look at some columns of the most expensive diamonds:
+
+    tv --select cut,color,price --sort price:desc diamonds.csv
+
+Count the values of a column:
+
+    tv --count cut diamonds.csv
+
+See which columns have missing values:
+
+    tv --na-report titanic.csv"#;
+
+/// One option of the --help, like `-n, --number-of-rows-to-output <row-display>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    spec: String,
+    text: String,
+}
+
+/// The FLAGS, OPTIONS and ARGS of the --help.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Section {
+    title: String,
+    entries: Vec<Entry>,
+}
+
+/// The usage line and the option sections of `help`, the --help of tv written
+/// without line wrapping.
+fn parse_help(help: &str) -> (String, Vec<Section>) {
+    let mut usage = String::new();
+    let mut sections: Vec<Section> = Vec::new();
+    let mut in_usage = false;
+    for line in help.lines() {
+        if !line.starts_with(' ') && line.ends_with(':') {
+            let title = line.trim_end_matches(':');
+            in_usage = title == "USAGE";
+            if !in_usage {
+                sections.push(Section {
+                    title: title.to_string(),
+                    entries: Vec::new(),
+                });
+            }
+            continue;
+        }
+        let text = line.trim();
+        if text.is_empty() {
+            continue;
+        }
+        if in_usage {
+            usage = text.to_string();
+            continue;
+        }
+        let Some(section) = sections.last_mut() else {
+            // the name, version and about before USAGE
+            continue;
+        };
+        if text.starts_with('-') || text.starts_with('<') {
+            let (spec, text) = text.split_once("  ").unwrap_or((text, ""));
+            section.entries.push(Entry {
+                spec: spec.to_string(),
+                text: text.trim().to_string(),
+            });
+        } else if let Some(entry) = section.entries.last_mut() {
+            // help written over several lines
+            if !entry.text.is_empty() {
+                entry.text.push(' ');
+            }
+            entry.text.push_str(text);
+        }
+    }
+    (usage, sections)
+}
+
+/// The man page of tv, in roff, from `help`, its --help without line wrapping.
+pub fn man_page(help: &str, version: &str) -> String {
+    let (usage, sections) = parse_help(help);
+    let mut page = format!(
+        ".TH TV 1 \"\" \"tv {}\" \"User Commands\"\n",
+        escape_roff(version)
+    );
+    page.push_str(".SH NAME\ntv \\- Tidy Viewer, a csv pretty printer\n");
+    page.push_str(&format!(".SH SYNOPSIS\n{}\n", escape_roff(&usage)));
+    page.push_str(&format!(".SH DESCRIPTION\n{}\n", escape_roff(DESCRIPTION)));
+    for section in &sections {
+        page.push_str(&format!(".SH {}\n", escape_roff(&section.title)));
+        for entry in &section.entries {
+            page.push_str(&format!(
+                ".TP\n\\fB{}\\fR\n{}\n",
+                escape_roff(&entry.spec),
+                escape_roff(&entry.text)
+            ));
+        }
+    }
+    for (title, text) in [
+        ("CONFIGURATION", CONFIGURATION),
+        ("COLORS", COLORS),
+        ("EXAMPLES", EXAMPLES),
+    ] {
+        page.push_str(&format!(".SH {}\n", title));
+        page.push_str(&roff_paragraphs(text));
+    }
+    page
+}
+
+/// The manual of tv as text that fits `width`, for a pager, from `help`, its
+/// --help without line wrapping.
+pub fn text(help: &str, version: &str, width: usize) -> String {
+    let (usage, sections) = parse_help(help);
+    let mut page = format!(
+        "tv {}\n\nNAME\n    tv - Tidy Viewer, a csv pretty printer\n",
+        version
+    );
+    page.push_str(&format!("\nSYNOPSIS\n    {}\n", usage));
+    page.push_str("\nDESCRIPTION\n");
+    page.push_str(&wrap(DESCRIPTION, 4, width));
+    for section in &sections {
+        page.push_str(&format!("\n{}\n", section.title));
+        for entry in &section.entries {
+            page.push_str(&format!("    {}\n", entry.spec));
+            page.push_str(&wrap(&entry.text, 8, width));
+        }
+    }
+    for (title, text) in [
+        ("CONFIGURATION", CONFIGURATION),
+        ("COLORS", COLORS),
+        ("EXAMPLES", EXAMPLES),
+    ] {
+        page.push_str(&format!("\n{}\n", title));
+        for line in text.lines() {
+            if line.starts_with("    ") {
+                // examples and the config are kept as written
+                page.push_str(&format!("    {}\n", line));
+            } else if line.is_empty() {
+                page.push('\n');
+            } else {
+                page.push_str(&wrap(line, 4, width));
+            }
+        }
+    }
+    page
+}
+
+// The lines of a section. Lines starting with * are a list and lines indented
+// by four spaces are shown as written.
+fn roff_paragraphs(text: &str) -> String {
+    let mut roff = String::new();
+    let mut is_literal = false;
+    for line in text.lines() {
+        let literal = line.starts_with("    ");
+        if literal != is_literal {
+            roff.push_str(if literal { ".RS\n.nf\n" } else { ".fi\n.RE\n" });
+            is_literal = literal;
+        }
+        if literal {
+            roff.push_str(&format!("{}\n", escape_roff(&line[4..])));
+        } else if let Some(item) = line.strip_prefix("* ") {
+            roff.push_str(&format!(".IP \\(bu 2\n{}\n", escape_roff(item)));
+        } else if line.is_empty() {
+            roff.push_str(".PP\n");
+        } else {
+            roff.push_str(&format!(".PP\n{}\n", escape_roff(line)));
+        }
+    }
+    if is_literal {
+        roff.push_str(".fi\n.RE\n");
+    }
+    roff
+}
+
+// Text that roff shows as written: backslashes and dashes are escaped and a
+// line can not start a request.
+fn escape_roff(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+// Words of `text` on lines indented by `indent` that fit `width` where they can.
+fn wrap(text: &str, indent: usize, width: usize) -> String {
+    let room = width.saturating_sub(indent).max(20);
+    let mut wrapped = String::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.width() + 1 + word.width() > room {
+            wrapped.push_str(&format!("{:indent$}{}\n", "", line, indent = indent));
+            line.clear();
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        wrapped.push_str(&format!("{:indent$}{}\n", "", line, indent = indent));
+    }
+    wrapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELP: &str = "tv 1.5.2
+Tidy Viewer (tv) is a csv pretty printer.
+
+USAGE:
+    tv [FLAGS] [OPTIONS] [FILE]...
+
+FLAGS:
+    -a, --color-always    Always force color output. Example `tv -a starwars.csv | less -R`
+        --glimpse         Show one line per column
+
+OPTIONS:
+    -c, --color <color>    There are 5 preconfigured color palettes (Defaults to nord):
+                (1)nord
+                (2)one_dark [default: 0]
+
+ARGS:
+    <FILE>...    File(s) to process
+";
+
+    #[test]
+    fn reads_the_options_of_the_help() {
+        let (usage, sections) = parse_help(HELP);
+        assert_eq!(usage, "tv [FLAGS] [OPTIONS] [FILE]...");
+        let titles: Vec<&str> = sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, ["FLAGS", "OPTIONS", "ARGS"]);
+        assert_eq!(
+            sections[0].entries[1],
+            Entry {
+                spec: "--glimpse".to_string(),
+                text: "Show one line per column".to_string(),
+            }
+        );
+        assert_eq!(
+            sections[1].entries[0].text,
+            "There are 5 preconfigured color palettes (Defaults to nord): (1)nord (2)one_dark [default: 0]"
+        );
+        assert_eq!(sections[2].entries[0].spec, "<FILE>...");
+    }
+
+    #[test]
+    fn writes_a_man_page() {
+        let page = man_page(HELP, "1.5.2");
+        assert!(page.starts_with(".TH TV 1 \"\" \"tv 1.5.2\" \"User Commands\"\n"));
+        assert!(page.contains(".SH FLAGS\n.TP\n\\fB\\-a, \\-\\-color\\-always\\fR\n"));
+        assert!(page.contains(".SH CONFIGURATION\n"));
+        assert!(page.contains(".RS\n.nf\n## ==Tidy\\-Viewer Config Example==\n"));
+        assert!(page.contains("{FOLDERID_RoamingAppData}\\etv.toml"));
+        // every literal block is closed
+        assert_eq!(page.matches(".nf\n").count(), page.matches(".fi\n").count());
+        assert_eq!(escape_roff(".hidden"), "\\&.hidden");
+    }
+
+    #[test]
+    fn writes_the_manual_as_text() {
+        let page = text(HELP, "1.5.2", 40);
+        assert!(
+            page.contains("\nFLAGS\n    -a, --color-always\n        Always force color output.\n")
+        );
+        assert!(page.contains("\n        #title = \"\"\n"));
+        // the help of the options is wrapped, the config and examples are kept
+        // as written
+        let options =
+            &page[page.find("\nFLAGS\n").unwrap()..page.find("\nCONFIGURATION\n").unwrap()];
+        for line in options.lines() {
+            assert!(line.width() <= 40, "{:?}", line);
+        }
+    }
+}
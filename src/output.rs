@@ -0,0 +1,717 @@
+// Output formats other than the table printed to the terminal.
+use serde_json::Value;
+use std::fmt::Write;
+use tidy_viewer::datatype::{self, CellRole, FormatOptions, ValueType};
+
+/// What tv prints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// The styled table for the terminal.
+    Table,
+    /// A standalone HTML page with the theme's colors as inline CSS.
+    Html,
+    /// The rows as JSON objects with typed values.
+    Json,
+    /// A booktabs tabular for LaTeX documents.
+    Latex,
+    /// The shown rows and columns as CSV, with the values of the file after conversions
+    /// like `--percent` or `--iso-dates`, unformatted.
+    Csv,
+    /// Like `Csv`, separated by tabs.
+    Tsv,
+    /// A GitHub flavored markdown table.
+    Markdown,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "html" => Ok(OutputFormat::Html),
+            "json" => Ok(OutputFormat::Json),
+            "latex" | "tex" => Ok(OutputFormat::Latex),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            _ => Err(format!(
+                "unknown output format \"{}\", expected table, html, json, latex, csv, tsv or markdown",
+                s
+            )),
+        }
+    }
+}
+
+/// The colors of the theme as RGB, before they are fitted to the terminal.
+pub struct Palette {
+    pub meta: [u8; 3],
+    pub header: [u8; 3],
+    pub std: [u8; 3],
+    pub na: [u8; 3],
+    pub neg_num: [u8; 3],
+}
+
+/// The text around the table.
+pub struct Notes<'a> {
+    pub title: Option<&'a str>,
+    /// `tv dim: rows x cols`, `None` with --no-dimensions.
+    pub dims: Option<String>,
+    /// `… with n more rows`, `None` if every row is shown.
+    pub more_rows: Option<String>,
+    pub footer: Option<&'a str>,
+}
+
+/// A standalone HTML page showing `rows`, the header first. Values are the
+/// formatted ones of the terminal table; `roles` pick the color of each cell. Rows
+/// are numbered after `row_numbers`, the rows left out before them, or not at
+/// all with `None`.
+pub fn to_html(
+    rows: &[Vec<String>],
+    roles: &[Vec<CellRole>],
+    row_numbers: Option<usize>,
+    palette: &Palette,
+    notes: &Notes,
+) -> String {
+    let meta = css_color(palette.meta);
+    // the themes are made for a terminal background, light or dark
+    let background = if luminance(palette.std) > 128.0 {
+        "#1e1e1e"
+    } else {
+        "#ffffff"
+    };
+    let cell = "padding: 0 0.5em; white-space: pre;";
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    let _ = writeln!(
+        out,
+        "<title>{}</title>",
+        escape(notes.title.unwrap_or("tv"))
+    );
+    let _ = writeln!(
+        out,
+        "</head>\n<body style=\"font-family: monospace; background: {};\">",
+        background
+    );
+    if let Some(title) = notes.title {
+        let _ = writeln!(
+            out,
+            "<p style=\"color: {}; font-weight: bold; text-decoration: underline;\">{}</p>",
+            meta,
+            escape(title)
+        );
+    }
+    if let Some(dims) = &notes.dims {
+        let _ = writeln!(out, "<p style=\"color: {};\">{}</p>", meta, escape(dims));
+    }
+
+    // emails often drop the style of the body, the table keeps its own
+    let _ = write!(
+        out,
+        "<table style=\"border-collapse: collapse; background: {};\">\n<thead>\n<tr>",
+        background
+    );
+    if row_numbers.is_some() {
+        let _ = write!(out, "<th style=\"{}\"></th>", cell);
+    }
+    for name in rows.first().into_iter().flatten() {
+        let _ = write!(
+            out,
+            "<th style=\"{} color: {}; text-align: left;\">{}</th>",
+            cell,
+            css_color(palette.header),
+            escape(name.trim())
+        );
+    }
+    out.push_str("</tr>\n</thead>\n<tbody>\n");
+    for (i, row) in rows.iter().enumerate().skip(1) {
+        out.push_str("<tr>");
+        if let Some(offset) = row_numbers {
+            let _ = write!(
+                out,
+                "<td style=\"{} color: {}; text-align: right;\">{}</td>",
+                cell,
+                meta,
+                offset + i
+            );
+        }
+        for (j, value) in row.iter().enumerate() {
+            let value = value.trim();
+            let (color, align) = match roles[i][j] {
+                CellRole::Missing => (palette.na, "left"),
+                CellRole::Negative => (palette.neg_num, "right"),
+                CellRole::Number => (palette.std, "right"),
+                CellRole::Text => (palette.std, "left"),
+            };
+            let _ = write!(
+                out,
+                "<td style=\"{} color: {}; text-align: {};\">{}</td>",
+                cell,
+                css_color(color),
+                align,
+                escape(value)
+            );
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</tbody>\n</table>\n");
+
+    if let Some(more_rows) = &notes.more_rows {
+        let _ = writeln!(
+            out,
+            "<p style=\"color: {};\">{}</p>",
+            meta,
+            escape(more_rows)
+        );
+    }
+    if let Some(footer) = notes.footer {
+        let _ = writeln!(out, "<p style=\"color: {};\">{}</p>", meta, escape(footer));
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// A booktabs tabular showing `rows`, the header first, wrapped in a table
+/// with a caption when there is a title. Values are the formatted ones of the
+/// terminal table. Number columns are split at the decimal point into an
+/// `r@{}l` pair so the points line up as they do in the terminal. Rows are
+/// numbered as in [`to_html`].
+pub fn to_latex(
+    rows: &[Vec<String>],
+    roles: &[Vec<CellRole>],
+    row_numbers: Option<usize>,
+    notes: &Notes,
+) -> String {
+    let names = rows.first().map_or(&[][..], |row| row.as_slice());
+    let numeric = numeric_columns(roles, names.len());
+
+    let mut spec = String::new();
+    if row_numbers.is_some() {
+        spec.push('r');
+    }
+    for &is_numeric in &numeric {
+        spec.push_str(if is_numeric { "r@{}l" } else { "l" });
+    }
+
+    let mut out = String::new();
+    if let Some(dims) = &notes.dims {
+        let _ = writeln!(out, "% {}", dims);
+    }
+    if let Some(title) = notes.title {
+        out.push_str("\\begin{table}\n\\centering\n");
+        let _ = writeln!(out, "\\caption{{{}}}", escape_latex(title));
+    }
+    let _ = writeln!(out, "\\begin{{tabular}}{{{}}}\n\\toprule", spec);
+
+    let mut header: Vec<String> = Vec::new();
+    if row_numbers.is_some() {
+        header.push(String::new());
+    }
+    for (name, &is_numeric) in names.iter().zip(&numeric) {
+        let name = escape_latex(name.trim());
+        header.push(if is_numeric {
+            format!("\\multicolumn{{2}}{{c}}{{{}}}", name)
+        } else {
+            name
+        });
+    }
+    let _ = writeln!(out, "{} \\\\\n\\midrule", header.join(" & "));
+
+    for (i, row) in rows.iter().enumerate().skip(1) {
+        let mut cells: Vec<String> = Vec::new();
+        if let Some(offset) = row_numbers {
+            cells.push((offset + i).to_string());
+        }
+        for (j, value) in row.iter().enumerate() {
+            let value = value.trim();
+            if !numeric[j] {
+                cells.push(escape_latex(value));
+            } else if roles[i][j] == CellRole::Missing {
+                cells.push(format!(
+                    "\\multicolumn{{2}}{{c}}{{{}}}",
+                    escape_latex(value)
+                ));
+            } else {
+                let (whole, fraction) = match value.find('.') {
+                    Some(point) => value.split_at(point),
+                    None => (value, ""),
+                };
+                let whole = match whole.strip_prefix('-') {
+                    Some(magnitude) => format!("$-${}", magnitude),
+                    None => whole.to_string(),
+                };
+                cells.push(format!("{} & {}", whole, fraction));
+            }
+        }
+        let _ = writeln!(out, "{} \\\\", cells.join(" & "));
+    }
+    out.push_str("\\bottomrule\n\\end{tabular}\n");
+    if notes.title.is_some() {
+        out.push_str("\\end{table}\n");
+    }
+    if let Some(more_rows) = &notes.more_rows {
+        let _ = writeln!(out, "% {}", more_rows);
+    }
+    if let Some(footer) = notes.footer {
+        let _ = writeln!(out, "% {}", footer);
+    }
+    out
+}
+
+/// A GitHub flavored markdown table showing `rows`, the header first, with
+/// number columns right aligned. The title is a bold line above the table and
+/// the other notes are lines around it. Rows are numbered as in [`to_html`].
+pub fn to_markdown(
+    rows: &[Vec<String>],
+    roles: &[Vec<CellRole>],
+    row_numbers: Option<usize>,
+    notes: &Notes,
+) -> String {
+    let names = rows.first().map_or(&[][..], |row| row.as_slice());
+    let numeric = numeric_columns(roles, names.len());
+
+    let mut out = String::new();
+    if let Some(title) = notes.title {
+        let _ = writeln!(out, "**{}**\n", escape_markdown(title));
+    }
+    if let Some(dims) = &notes.dims {
+        let _ = writeln!(out, "{}\n", escape_markdown(dims));
+    }
+
+    let mut header: Vec<String> = Vec::new();
+    let mut rule: Vec<&str> = Vec::new();
+    if row_numbers.is_some() {
+        header.push(String::new());
+        rule.push("--:");
+    }
+    for (name, &is_numeric) in names.iter().zip(&numeric) {
+        header.push(escape_markdown(name.trim()));
+        rule.push(if is_numeric { "--:" } else { "---" });
+    }
+    let _ = writeln!(out, "| {} |", header.join(" | "));
+    let _ = writeln!(out, "| {} |", rule.join(" | "));
+    for (i, row) in rows.iter().enumerate().skip(1) {
+        let mut cells: Vec<String> = Vec::new();
+        if let Some(offset) = row_numbers {
+            cells.push((offset + i).to_string());
+        }
+        cells.extend(row.iter().map(|value| escape_markdown(value.trim())));
+        let _ = writeln!(out, "| {} |", cells.join(" | "));
+    }
+
+    if let Some(more_rows) = &notes.more_rows {
+        let _ = writeln!(out, "\n{}", escape_markdown(more_rows));
+    }
+    if let Some(footer) = notes.footer {
+        let _ = writeln!(out, "\n{}", escape_markdown(footer));
+    }
+    out
+}
+
+// A column is numeric when its values that are not missing, and there is one,
+// are all numbers.
+fn numeric_columns(roles: &[Vec<CellRole>], columns: usize) -> Vec<bool> {
+    (0..columns)
+        .map(|j| {
+            let mut values = roles
+                .iter()
+                .skip(1)
+                .map(|row| row[j])
+                .filter(|&role| role != CellRole::Missing)
+                .peekable();
+            values.peek().is_some()
+                && values.all(|role| matches!(role, CellRole::Number | CellRole::Negative))
+        })
+        .collect()
+}
+
+/// The rows of `columns` as a JSON array of objects keyed by the header, the
+/// first value of each column. Values are typed with the column's inferred
+/// type: numbers and logicals as JSON numbers and booleans, missing values as
+/// null, anything else as a string.
+pub fn to_json(columns: &[Vec<&str>], opts: &FormatOptions) -> String {
+    let rows = columns.first().map_or(0, |col| col.len());
+    let types: Vec<ValueType> = columns
+        .iter()
+        .map(|col| column_type(&col[1.min(col.len())..], opts))
+        .collect();
+
+    let mut out = String::from("[\n");
+    for row in 1..rows {
+        crate::crash::note_record(row, None, columns.iter().map(|col| col[row]));
+        let fields: Vec<String> = columns
+            .iter()
+            .zip(&types)
+            .map(|(col, &dtype)| {
+                format!(
+                    "{}: {}",
+                    Value::from(col[0]),
+                    json_value(col[row], dtype, opts)
+                )
+            })
+            .collect();
+        let comma = if row + 1 < rows { "," } else { "" };
+        let _ = writeln!(out, "  {{{}}}{}", fields.join(", "), comma);
+    }
+    out.push_str("]\n");
+    out
+}
+
+/// Which fields of CSV output are quoted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quote {
+    /// Only fields with a delimiter, quote or line break.
+    #[default]
+    Necessary,
+    Always,
+    /// Every field that is not a number.
+    NonNumeric,
+    /// No field, even if it makes the output ambiguous.
+    Never,
+}
+
+impl std::str::FromStr for Quote {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "necessary" => Ok(Quote::Necessary),
+            "always" => Ok(Quote::Always),
+            "non-numeric" => Ok(Quote::NonNumeric),
+            "never" => Ok(Quote::Never),
+            _ => Err(format!(
+                "unknown quoting \"{}\", expected necessary, always, non-numeric or never",
+                s
+            )),
+        }
+    }
+}
+
+/// The delimiter and quoting of CSV output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CsvStyle {
+    pub delimiter: u8,
+    pub quote: Quote,
+}
+
+impl Default for CsvStyle {
+    fn default() -> Self {
+        CsvStyle {
+            delimiter: b',',
+            quote: Quote::Necessary,
+        }
+    }
+}
+
+/// Parse the delimiter of CSV output, one byte written like the input delimiter
+/// (`;`, `tab`, `\x01`).
+pub fn parse_output_delimiter(src: &str) -> Result<u8, String> {
+    match *datatype::parse_delimiter(src)? {
+        [delimiter] => Ok(delimiter),
+        _ => Err(format!(
+            "the output delimiter must be one byte, got \"{}\"",
+            src
+        )),
+    }
+}
+
+/// The rows of `columns` as CSV, the header first.
+pub fn to_csv(columns: &[Vec<&str>], style: CsvStyle) -> csv::Result<Vec<u8>> {
+    let rows = columns.first().map_or(0, |col| col.len());
+    let quote = match style.quote {
+        Quote::Necessary => csv::QuoteStyle::Necessary,
+        Quote::Always => csv::QuoteStyle::Always,
+        Quote::NonNumeric => csv::QuoteStyle::NonNumeric,
+        Quote::Never => csv::QuoteStyle::Never,
+    };
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(style.delimiter)
+        .quote_style(quote)
+        .from_writer(Vec::new());
+    for row in 0..rows {
+        writer.write_record(columns.iter().map(|col| col[row]))?;
+    }
+    writer
+        .into_inner()
+        .map_err(|e| csv::Error::from(std::io::Error::other(e.to_string())))
+}
+
+/// The type of a column from its values, `Na` when every value is missing.
+pub fn column_type(values: &[&str], opts: &FormatOptions) -> ValueType {
+    // get_col_data_type needs at least one value that is not NA
+    if values
+        .iter()
+        .all(|v| datatype::infer_type_from_string(v, opts) == ValueType::Na)
+    {
+        ValueType::Na
+    } else {
+        datatype::get_col_data_type(values, opts)
+    }
+}
+
+fn json_value(text: &str, dtype: ValueType, opts: &FormatOptions) -> Value {
+    if datatype::is_missing(text, opts) {
+        return Value::Null;
+    }
+    let trimmed = text.trim();
+    let typed = match dtype {
+        // integer columns can hold a few doubles and the other way around
+        ValueType::Integer | ValueType::Double => trimmed
+            .trim_start_matches('+')
+            .parse::<i64>()
+            .ok()
+            .map(Value::from)
+            .or_else(|| {
+                datatype::parse_radix_integer(trimmed)
+                    .and_then(|n| i64::try_from(n).ok())
+                    .map(Value::from)
+            })
+            .or_else(|| {
+                trimmed
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(Value::Number)
+            }),
+        ValueType::Boolean => datatype::parse_logical(trimmed, opts).map(Value::Bool),
+        _ => None,
+    };
+    // a value that does not fit the type of its column is kept as text
+    typed.unwrap_or_else(|| Value::from(text))
+}
+
+fn css_color(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+fn luminance(rgb: [u8; 3]) -> f64 {
+    0.299 * f64::from(rgb[0]) + 0.587 * f64::from(rgb[1]) + 0.114 * f64::from(rgb[2])
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_latex(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// Markdown syntax in a value is shown as written, and a pipe does not end the cell.
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_a_standalone_html_table() {
+        let rows = vec![
+            vec!["name ".to_string(), "delta".to_string()],
+            vec!["<a&b>".to_string(), "   -1".to_string()],
+            vec!["NA   ".to_string(), "    2".to_string()],
+        ];
+        let roles = vec![
+            vec![CellRole::Text, CellRole::Text],
+            vec![CellRole::Text, CellRole::Negative],
+            vec![CellRole::Missing, CellRole::Number],
+        ];
+        let palette = Palette {
+            meta: [1, 2, 3],
+            header: [255, 0, 0],
+            std: [0, 255, 0],
+            na: [0, 0, 255],
+            neg_num: [16, 32, 48],
+        };
+        let notes = Notes {
+            title: Some("Deltas"),
+            dims: Some("tv dim: 2 x 2".to_string()),
+            more_rows: None,
+            footer: None,
+        };
+        let html = to_html(&rows, &roles, Some(0), &palette, &notes);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<table style=\"border-collapse: collapse; background: #1e1e1e;\">"));
+        assert!(html.contains("<title>Deltas</title>"));
+        assert!(html.contains("<p style=\"color: #010203;\">tv dim: 2 x 2</p>"));
+        assert!(html.contains("color: #ff0000; text-align: left;\">name</th>"));
+        assert!(html.contains("color: #00ff00; text-align: left;\">&lt;a&amp;b&gt;</td>"));
+        assert!(html.contains("color: #102030; text-align: right;\">-1</td>"));
+        assert!(html.contains("color: #0000ff; text-align: left;\">NA</td>"));
+        assert!(html.contains("color: #010203; text-align: right;\">2</td>"));
+        assert!(html.ends_with("</html>\n"));
+        assert_eq!("HTML".parse(), Ok(OutputFormat::Html));
+        assert_eq!("json".parse(), Ok(OutputFormat::Json));
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn writes_a_booktabs_table() {
+        let rows = vec![
+            vec!["name  ".to_string(), "delta ".to_string(), "n ".to_string()],
+            vec!["a_b   ".to_string(), "  1.50".to_string(), "10".to_string()],
+            vec!["100%  ".to_string(), "-22.2 ".to_string(), "NA".to_string()],
+        ];
+        let roles = vec![
+            vec![CellRole::Text, CellRole::Text, CellRole::Text],
+            vec![CellRole::Text, CellRole::Number, CellRole::Number],
+            vec![CellRole::Text, CellRole::Negative, CellRole::Missing],
+        ];
+        let notes = Notes {
+            title: Some("Deltas & more"),
+            dims: Some("tv dim: 2 x 3".to_string()),
+            more_rows: None,
+            footer: None,
+        };
+        assert_eq!(
+            to_latex(&rows, &roles, Some(10), &notes),
+            concat!(
+                "% tv dim: 2 x 3\n",
+                "\\begin{table}\n",
+                "\\centering\n",
+                "\\caption{Deltas \\& more}\n",
+                "\\begin{tabular}{rlr@{}lr@{}l}\n",
+                "\\toprule\n",
+                " & name & \\multicolumn{2}{c}{delta} & \\multicolumn{2}{c}{n} \\\\\n",
+                "\\midrule\n",
+                "11 & a\\_b & 1 & .50 & 10 &  \\\\\n",
+                "12 & 100\\% & $-$22 & .2 & \\multicolumn{2}{c}{NA} \\\\\n",
+                "\\bottomrule\n",
+                "\\end{tabular}\n",
+                "\\end{table}\n",
+            )
+        );
+        let notes = Notes {
+            title: None,
+            dims: None,
+            more_rows: None,
+            footer: None,
+        };
+        assert!(to_latex(&rows, &roles, None, &notes).starts_with("\\begin{tabular}{lr@{}lr@{}l}"));
+        assert_eq!("tex".parse(), Ok(OutputFormat::Latex));
+    }
+
+    #[test]
+    fn writes_typed_json() {
+        let columns = vec![
+            vec!["name", "Ann", "Bob \"B\"", "Cy"],
+            vec!["age", "007", "NA", "-4"],
+            vec!["score", "1.5", "2", "1e3"],
+            vec!["count", "1", "2", "2.5"],
+            vec!["ok", "TRUE", "f", ""],
+            vec!["id", "0x1F", "12", "x"],
+            vec!["empty", "", "NA", "null"],
+        ];
+        let json = to_json(&columns, &FormatOptions::default());
+        assert_eq!(
+            json,
+            concat!(
+                "[\n",
+                "  {\"name\": \"Ann\", \"age\": 7, \"score\": 1.5, \"count\": 1, \"ok\": true, \"id\": 31, \"empty\": null},\n",
+                "  {\"name\": \"Bob \\\"B\\\"\", \"age\": null, \"score\": 2, \"count\": 2, \"ok\": false, \"id\": 12, \"empty\": null},\n",
+                "  {\"name\": \"Cy\", \"age\": -4, \"score\": 1000.0, \"count\": 2.5, \"ok\": null, \"id\": \"x\", \"empty\": null}\n",
+                "]\n",
+            )
+        );
+        let literal = FormatOptions {
+            na_strings: false,
+            ..FormatOptions::default()
+        };
+        assert!(to_json(&columns, &literal).contains("\"age\": \"NA\""));
+        assert_eq!(to_json(&[vec!["a"]], &literal), "[\n]\n");
+    }
+
+    #[test]
+    fn writes_csv() {
+        let columns = vec![vec!["name", "Ann", "Bob, \"B\""], vec!["age", "007", ""]];
+        assert_eq!(
+            to_csv(&columns, CsvStyle::default()).unwrap(),
+            b"name,age\nAnn,007\n\"Bob, \"\"B\"\"\",\n".to_vec()
+        );
+        assert_eq!("CSV".parse(), Ok(OutputFormat::Csv));
+    }
+
+    #[test]
+    fn writes_csv_with_a_delimiter_and_quoting() {
+        let columns = vec![vec!["name", "Ann;B", "Bob"], vec!["price", "3.5", "7"]];
+        let style = CsvStyle {
+            delimiter: b'\t',
+            quote: Quote::NonNumeric,
+        };
+        assert_eq!(
+            to_csv(&columns, style).unwrap(),
+            b"\"name\"\t\"price\"\n\"Ann;B\"\t3.5\n\"Bob\"\t7\n".to_vec()
+        );
+        let style = CsvStyle {
+            delimiter: b';',
+            quote: Quote::Necessary,
+        };
+        assert_eq!(
+            to_csv(&columns, style).unwrap(),
+            b"name;price\n\"Ann;B\";3.5\nBob;7\n".to_vec()
+        );
+        assert_eq!(parse_output_delimiter("semicolon"), Ok(b';'));
+        assert_eq!(parse_output_delimiter("\\t"), Ok(b'\t'));
+        assert!(parse_output_delimiter("||").is_err());
+        assert_eq!("non-numeric".parse(), Ok(Quote::NonNumeric));
+        assert_eq!("TSV".parse(), Ok(OutputFormat::Tsv));
+    }
+
+    #[test]
+    fn writes_a_markdown_table() {
+        let rows = vec![
+            vec!["name  ".to_string(), "delta ".to_string()],
+            vec!["a|b   ".to_string(), "  1.50".to_string()],
+            vec!["x_y   ".to_string(), "NA    ".to_string()],
+        ];
+        let roles = vec![
+            vec![CellRole::Text, CellRole::Text],
+            vec![CellRole::Text, CellRole::Number],
+            vec![CellRole::Text, CellRole::Missing],
+        ];
+        let notes = Notes {
+            title: Some("Deltas"),
+            dims: None,
+            more_rows: Some("\u{2026} with 8 more rows".to_string()),
+            footer: None,
+        };
+        assert_eq!(
+            to_markdown(&rows, &roles, Some(0), &notes),
+            concat!(
+                "**Deltas**\n",
+                "\n",
+                "|  | name | delta |\n",
+                "| --: | --- | --: |\n",
+                "| 1 | a\\|b | 1.50 |\n",
+                "| 2 | x\\_y | NA |\n",
+                "\n",
+                "\u{2026} with 8 more rows\n",
+            )
+        );
+        assert_eq!("md".parse(), Ok(OutputFormat::Markdown));
+    }
+}
@@ -0,0 +1,123 @@
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// True if `path` is an `http://` or `https://` URL rather than a local file.
+pub fn is_url(path: &Path) -> bool {
+    match path.to_str() {
+        Some(s) => {
+            let s = s.to_ascii_lowercase();
+            s.starts_with("http://") || s.starts_with("https://")
+        }
+        None => false,
+    }
+}
+
+/// The path component of `url` without query string or fragment, so that
+/// `https://host/data.tsv.gz?raw=1` is detected like a local `data.tsv.gz`.
+pub fn url_path(url: &Path) -> PathBuf {
+    let url = url.to_string_lossy();
+    let without_scheme = url.split_once("://").map_or(&*url, |(_, rest)| rest);
+    let path = without_scheme.split(['?', '#']).next().unwrap_or("");
+    match path.split_once('/') {
+        Some((_host, path)) => PathBuf::from(path),
+        None => PathBuf::new(),
+    }
+}
+
+/// Start downloading `url`. The body is streamed, not buffered up front.
+#[cfg(feature = "http")]
+pub fn open(url: &Path) -> io::Result<Box<dyn Read>> {
+    let response = ureq::get(&url.to_string_lossy())
+        .call()
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    Ok(Box::new(io::BufReader::new(response.into_reader())))
+}
+
+#[cfg(not(feature = "http"))]
+pub fn open(url: &Path) -> io::Result<Box<dyn Read>> {
+    Err(io::Error::other(format!(
+        "{}: reading http:// and https:// URLs needs tv built with `--features http`",
+        url.display()
+    )))
+}
+
+/// True if `path` is an object storage URL: `s3://bucket/key` or `gs://bucket/key`.
+pub fn is_object_url(path: &Path) -> bool {
+    match path.to_str() {
+        Some(s) => {
+            let s = s.to_ascii_lowercase();
+            s.starts_with("s3://") || s.starts_with("gs://")
+        }
+        None => false,
+    }
+}
+
+/// Fetch an object from S3 or GCS. Credentials, region and endpoint come from
+/// the usual environment variables (`AWS_ACCESS_KEY_ID`, `AWS_REGION`,
+/// `GOOGLE_SERVICE_ACCOUNT`, ...).
+#[cfg(feature = "object-store")]
+pub fn open_object(url: &Path) -> io::Result<Box<dyn Read>> {
+    use object_store::aws::AmazonS3Builder;
+    use object_store::gcp::GoogleCloudStorageBuilder;
+    use object_store::ObjectStore;
+
+    let url = url.to_string_lossy();
+    let (scheme, rest) = url.split_once("://").unwrap_or(("", &url));
+    let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+    let bucket_url = format!("{}://{}", scheme, bucket);
+    let store: Box<dyn ObjectStore> = if scheme.eq_ignore_ascii_case("s3") {
+        Box::new(AmazonS3Builder::from_env().with_url(bucket_url).build()?)
+    } else {
+        Box::new(
+            GoogleCloudStorageBuilder::from_env()
+                .with_url(bucket_url)
+                .build()?,
+        )
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let bytes = runtime.block_on(async {
+        let location = object_store::path::Path::from(key);
+        store.get(&location).await?.bytes().await
+    })?;
+    Ok(Box::new(io::Cursor::new(bytes)))
+}
+
+#[cfg(not(feature = "object-store"))]
+pub fn open_object(url: &Path) -> io::Result<Box<dyn Read>> {
+    Err(io::Error::other(format!(
+        "{}: reading s3:// and gs:// URLs needs tv built with `--features object-store`",
+        url.display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_urls_and_their_extension() {
+        assert!(is_url(Path::new("https://example.com/data.csv")));
+        assert!(is_url(Path::new("HTTP://example.com/data.csv")));
+        assert!(!is_url(Path::new("data.csv")));
+        assert!(!is_url(Path::new("ftp://example.com/data.csv")));
+        assert!(!is_url(Path::new("s3://bucket/data.csv")));
+        assert!(is_object_url(Path::new("s3://bucket/data.csv")));
+        assert!(is_object_url(Path::new("gs://bucket/data.csv")));
+        assert!(!is_object_url(Path::new("https://example.com/data.csv")));
+
+        let ext = |u: &str| url_path(Path::new(u)).extension().map(|e| e.to_owned());
+        assert_eq!(ext("https://example.com/a/data.tsv"), Some("tsv".into()));
+        assert_eq!(
+            ext("https://example.com/data.psv?raw=1#top"),
+            Some("psv".into())
+        );
+        assert_eq!(ext("https://example.com"), None);
+        assert_eq!(
+            url_path(Path::new("https://example.com/data.csv.gz")),
+            PathBuf::from("data.csv.gz")
+        );
+    }
+}
@@ -0,0 +1,232 @@
+// The table for the terminal is laid out as lines of styled text, and only turned
+// into escape codes, or left plain, when it is written. The layout never deals
+// with colors, so it reads the same with and without them.
+use owo_colors::{DynColors, OwoColorize};
+use regex::Regex;
+use std::io::{self, Write};
+use tidy_viewer::datatype::CellRole;
+
+/// What a piece of the table is, which picks how it looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellStyle {
+    /// Spaces and separators, never colored.
+    Plain,
+    /// A value.
+    Standard,
+    /// A missing value.
+    Na,
+    /// A number below zero.
+    NegativeNumber,
+    /// A column name.
+    Header,
+    /// The name of a column with mostly missing values, see --na-threshold.
+    NaHeader,
+    /// Row numbers, the tv dim line and the notes below the table.
+    Meta,
+    Title,
+    /// A note that should not draw the eye, like the precision hint.
+    Hint,
+}
+
+impl CellStyle {
+    /// The style of a value with `role`.
+    pub fn of_cell(role: CellRole) -> CellStyle {
+        match role {
+            CellRole::Missing => CellStyle::Na,
+            CellRole::Negative => CellStyle::NegativeNumber,
+            CellRole::Text | CellRole::Number => CellStyle::Standard,
+        }
+    }
+}
+
+/// A piece of text and its style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: CellStyle,
+}
+
+pub fn span(text: impl Into<String>, style: CellStyle) -> Span {
+    Span {
+        text: text.into(),
+        style,
+    }
+}
+
+/// A line of the table, without its line break.
+pub type Line = Vec<Span>;
+
+/// The colors of the styles, fitted to the terminal.
+#[derive(Debug, Clone, Copy)]
+pub struct Colors {
+    pub meta: DynColors,
+    pub header: DynColors,
+    pub std: DynColors,
+    pub na: DynColors,
+    pub neg_num: DynColors,
+    pub highlight: DynColors,
+}
+
+/// Writes styled lines, with escape codes when `colors` are given.
+pub struct Renderer<'a> {
+    pub colors: Option<Colors>,
+    /// The matches of --highlight in values.
+    pub highlight: Option<&'a Regex>,
+}
+
+impl Renderer<'_> {
+    /// `span` as it is written.
+    pub fn paint(&self, span: &Span) -> String {
+        let Some(colors) = self.colors else {
+            return span.text.clone();
+        };
+        let text = span.text.as_str();
+        let value_color = match span.style {
+            CellStyle::Standard => Some(colors.std),
+            CellStyle::Na => Some(colors.na),
+            CellStyle::NegativeNumber => Some(colors.neg_num),
+            _ => None,
+        };
+        if let Some(color) = value_color {
+            return match self.highlight {
+                Some(pattern) => highlight(text, pattern, color, colors.highlight),
+                None => text.color(color).to_string(),
+            };
+        }
+        match span.style {
+            CellStyle::Plain => text.to_string(),
+            CellStyle::Header => text.color(colors.header).bold().to_string(),
+            CellStyle::NaHeader => text.color(colors.na).bold().to_string(),
+            CellStyle::Title => text.color(colors.meta).underline().bold().to_string(),
+            CellStyle::Hint => text.color(colors.meta).dimmed().to_string(),
+            _ => text.color(colors.meta).to_string(),
+        }
+    }
+
+    /// Write `lines`, each followed by a line break.
+    pub fn write(&self, out: &mut dyn Write, lines: &[Line]) -> io::Result<()> {
+        for line in lines {
+            for span in line {
+                out.write_all(self.paint(span).as_bytes())?;
+            }
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+/// A cell in `color` with the matches of `pattern` in bold `highlight_color`.
+fn highlight(cell: &str, pattern: &Regex, color: DynColors, highlight_color: DynColors) -> String {
+    let mut text = String::new();
+    let mut last = 0;
+    for found in pattern
+        .find_iter(cell)
+        .filter(|found| found.start() < found.end())
+    {
+        if last < found.start() {
+            text.push_str(&(&cell[last..found.start()]).color(color).to_string());
+        }
+        text.push_str(&found.as_str().color(highlight_color).bold().to_string());
+        last = found.end();
+    }
+    if last < cell.len() {
+        text.push_str(&(&cell[last..]).color(color).to_string());
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn colors() -> Colors {
+        Colors {
+            meta: DynColors::Rgb(1, 1, 1),
+            header: DynColors::Rgb(2, 2, 2),
+            std: DynColors::Rgb(3, 3, 3),
+            na: DynColors::Rgb(4, 4, 4),
+            neg_num: DynColors::Rgb(5, 5, 5),
+            highlight: DynColors::Rgb(6, 6, 6),
+        }
+    }
+
+    #[test]
+    fn writes_plain_or_styled_lines() {
+        let lines = vec![
+            vec![
+                span("  ", CellStyle::Plain),
+                span("name ", CellStyle::Header),
+            ],
+            vec![
+                span("   1", CellStyle::Meta),
+                span("  ", CellStyle::Plain),
+                span("NA ", CellStyle::Na),
+            ],
+        ];
+        let plain = Renderer {
+            colors: None,
+            highlight: None,
+        };
+        let mut out = Vec::new();
+        plain.write(&mut out, &lines).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "  name \n   1  NA \n");
+
+        let colors = colors();
+        let styled = Renderer {
+            colors: Some(colors),
+            highlight: None,
+        };
+        let mut out = Vec::new();
+        styled.write(&mut out, &lines).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            format!(
+                "  {}\n{}  {}\n",
+                "name ".color(colors.header).bold(),
+                "   1".color(colors.meta),
+                "NA ".color(colors.na)
+            )
+        );
+    }
+
+    #[test]
+    fn styles_cells_by_role() {
+        assert_eq!(CellStyle::of_cell(CellRole::Missing), CellStyle::Na);
+        assert_eq!(
+            CellStyle::of_cell(CellRole::Negative),
+            CellStyle::NegativeNumber
+        );
+        assert_eq!(CellStyle::of_cell(CellRole::Number), CellStyle::Standard);
+        assert_eq!(CellStyle::of_cell(CellRole::Text), CellStyle::Standard);
+    }
+
+    #[test]
+    fn highlights_matches_in_a_cell() {
+        let std = DynColors::Rgb(1, 1, 1);
+        let mark = DynColors::Rgb(2, 2, 2);
+        let pattern = Regex::new("a+").unwrap();
+        assert_eq!(
+            highlight("baab ", &pattern, std, mark),
+            format!(
+                "{}{}{}",
+                "b".color(std),
+                "aa".color(mark).bold(),
+                "b ".color(std)
+            )
+        );
+        let nothing = Regex::new("z*").unwrap();
+        assert_eq!(
+            highlight("ab", &nothing, std, mark),
+            "ab".color(std).to_string()
+        );
+        // only values are highlighted
+        let renderer = Renderer {
+            colors: Some(colors()),
+            highlight: Some(&pattern),
+        };
+        assert_eq!(
+            renderer.paint(&span("a", CellStyle::Header)),
+            "a".color(colors().header).bold().to_string()
+        );
+    }
+}
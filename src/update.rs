@@ -0,0 +1,252 @@
+// Compare the running tv with the latest release on GitHub and, with the
+// self-update feature, replace it by that release.
+use serde::Deserialize;
+use std::io;
+
+const LATEST_RELEASE: &str = "https://api.github.com/repos/alexhallam/tv/releases/latest";
+pub const RELEASES_PAGE: &str = "https://github.com/alexhallam/tv/releases/latest";
+
+/// A release on GitHub, as its API describes it.
+#[derive(Debug, Deserialize)]
+#[cfg_attr(not(feature = "self-update"), allow(dead_code))]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+/// A file attached to a release, like `tidy-viewer-1.5.2-x86_64-apple-darwin.tar.gz`.
+#[derive(Debug, Deserialize)]
+#[cfg_attr(not(feature = "self-update"), allow(dead_code))]
+pub struct Asset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+/// The latest release of tv on GitHub.
+pub fn latest_release() -> io::Result<Release> {
+    let response = ureq::get(LATEST_RELEASE)
+        .set("User-Agent", concat!("tv/", env!("CARGO_PKG_VERSION")))
+        .set("Accept", "application/vnd.github+json")
+        .call()
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    serde_json::from_reader(response.into_reader()).map_err(io::Error::other)
+}
+
+/// The major, minor and patch numbers of a version or tag like `v1.5.2`.
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let version = version.trim().trim_start_matches('v');
+    // a pre-release or build suffix is not compared
+    let version = version.split(['-', '+']).next()?;
+    let mut numbers = version.split('.').map(|n| n.parse::<u64>().ok());
+    let major = numbers.next()??;
+    let minor = numbers.next().unwrap_or(Some(0))?;
+    let patch = numbers.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch))
+}
+
+/// True if the release tagged `latest` is newer than `current`.
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (parse_version(latest), parse_version(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// The target of the release archive that runs here. Windows releases are
+/// zip files and are not installed by self-update, and there are no builds for
+/// other architectures, like aarch64 macOS.
+#[cfg(feature = "self-update")]
+fn release_target() -> Option<&'static str> {
+    if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Some("x86_64-unknown-linux-musl")
+    } else if cfg!(all(target_os = "linux", target_arch = "arm")) {
+        Some("arm-unknown-linux-gnueabihf")
+    } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+        Some("x86_64-apple-darwin")
+    } else {
+        None
+    }
+}
+
+/// Replace the running executable by the one of `release`.
+#[cfg(feature = "self-update")]
+pub fn install(release: &Release) -> io::Result<()> {
+    use std::fs;
+
+    let target = release_target().ok_or_else(|| {
+        io::Error::other(format!(
+            "there is no release to install on this platform, download it from {}",
+            RELEASES_PAGE
+        ))
+    })?;
+    let suffix = format!("-{}.tar.gz", target);
+    let asset = release
+        .assets
+        .iter()
+        .find(|asset| asset.name.starts_with("tidy-viewer-") && asset.name.ends_with(&suffix))
+        .ok_or_else(|| {
+            io::Error::other(format!("{} has no {} archive", release.tag_name, target))
+        })?;
+    let checksum_name = format!("{}.sha256", asset.name);
+    let checksum = release
+        .assets
+        .iter()
+        .find(|checksum| checksum.name == checksum_name)
+        .ok_or_else(|| {
+            io::Error::other(format!(
+                "{} publishes no checksum of {}, download it from {}",
+                release.tag_name, asset.name, RELEASES_PAGE
+            ))
+        })?;
+    let checksum = String::from_utf8_lossy(&download(&checksum.browser_download_url)?).into_owned();
+    let archive = download(&asset.browser_download_url)?;
+    if !checksum_matches(&archive, &checksum) {
+        return Err(io::Error::other(format!(
+            "{} does not match its published checksum, it was not installed",
+            asset.name
+        )));
+    }
+
+    let exe = std::env::current_exe()?;
+    // written next to the executable, so it can be renamed over it
+    let new_exe = exe.with_extension("new");
+    if !unpack_executable(&archive[..], &new_exe)? {
+        return Err(io::Error::other(format!(
+            "{} has no tidy-viewer executable",
+            asset.name
+        )));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&new_exe, fs::Permissions::from_mode(0o755))?;
+    }
+    fs::rename(&new_exe, &exe)
+}
+
+/// The body of a GET request to `url`.
+#[cfg(feature = "self-update")]
+fn download(url: &str) -> io::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let response = ureq::get(url)
+        .set("User-Agent", concat!("tv/", env!("CARGO_PKG_VERSION")))
+        .call()
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body)?;
+    Ok(body)
+}
+
+/// True if the SHA-256 of `archive` is the one of `checksum`, a line of
+/// `shasum -a 256` like `<hex digest>  <file name>`.
+#[cfg(feature = "self-update")]
+fn checksum_matches(archive: &[u8], checksum: &str) -> bool {
+    use sha2::{Digest, Sha256};
+
+    let expected = checksum.split_whitespace().next().unwrap_or("");
+    expected.len() == 64 && expected.eq_ignore_ascii_case(&format!("{:x}", Sha256::digest(archive)))
+}
+
+/// Write the tidy-viewer executable of a release archive, a .tar.gz, to `path`.
+/// False if the archive has none.
+#[cfg(feature = "self-update")]
+fn unpack_executable(archive: impl io::Read, path: &std::path::Path) -> io::Result<bool> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.file_name() == Some("tidy-viewer".as_ref()) {
+            entry.unpack(path)?;
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_versions() {
+        assert_eq!(parse_version("1.5.2"), Some((1, 5, 2)));
+        assert_eq!(parse_version("v1.6"), Some((1, 6, 0)));
+        assert_eq!(parse_version("2.0.0-beta.1"), Some((2, 0, 0)));
+        assert_eq!(parse_version("latest"), None);
+        assert!(is_newer("v1.5.10", "1.5.2"));
+        assert!(is_newer("2.0.0", "1.9.9"));
+        assert!(!is_newer("1.5.2", "1.5.2"));
+        assert!(!is_newer("1.4.6", "1.5.2"));
+        assert!(!is_newer("nightly", "1.5.2"));
+    }
+
+    #[cfg(feature = "self-update")]
+    #[test]
+    fn unpacks_the_executable_of_an_archive() {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in [
+            ("tidy-viewer-1.6.0-x86_64-apple-darwin/README.md", "readme"),
+            (
+                "tidy-viewer-1.6.0-x86_64-apple-darwin/tidy-viewer",
+                "binary",
+            ),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, contents.as_bytes())
+                .unwrap();
+        }
+        let tar = builder.into_inner().unwrap();
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        io::Write::write_all(&mut gz, &tar).unwrap();
+        let archive = gz.finish().unwrap();
+
+        let path = std::env::temp_dir().join(format!("tv-update-test-{}", std::process::id()));
+        assert!(unpack_executable(&archive[..], &path).unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "binary");
+        std::fs::remove_file(&path).unwrap();
+        let empty = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let empty = tar::Builder::new(empty)
+            .into_inner()
+            .unwrap()
+            .finish()
+            .unwrap();
+        assert!(!unpack_executable(&empty[..], &path).unwrap());
+    }
+
+    #[cfg(feature = "self-update")]
+    #[test]
+    fn checks_the_checksum_of_an_archive() {
+        // the SHA-256 of "abc"
+        let digest = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        let line = format!("{}  tidy-viewer-1.6.0-x86_64-apple-darwin.tar.gz\n", digest);
+        assert!(checksum_matches(b"abc", &line));
+        assert!(checksum_matches(b"abc", &digest.to_uppercase()));
+        assert!(!checksum_matches(b"abd", &line));
+        assert!(!checksum_matches(b"abc", ""));
+        assert!(!checksum_matches(b"abc", "Not Found"));
+    }
+
+    #[test]
+    fn reads_a_release() {
+        let json = r#"{
+            "tag_name": "1.6.0",
+            "name": "1.6.0",
+            "assets": [{
+                "name": "tidy-viewer-1.6.0-x86_64-apple-darwin.tar.gz",
+                "browser_download_url": "https://github.com/alexhallam/tv/releases/download/1.6.0/tidy-viewer-1.6.0-x86_64-apple-darwin.tar.gz",
+                "size": 1234
+            }]
+        }"#;
+        let release: Release = serde_json::from_str(json).unwrap();
+        assert_eq!(release.tag_name, "1.6.0");
+        assert_eq!(
+            release.assets[0].name,
+            "tidy-viewer-1.6.0-x86_64-apple-darwin.tar.gz"
+        );
+    }
+}